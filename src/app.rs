@@ -1,24 +1,303 @@
-use crate::util::{Cache, PlotData};
-use rustfft::{num_complex::Complex, FftPlanner};
-use std::sync::Mutex;
-use wavegen::{sawtooth, sine, square, PeriodicFunction, Waveform};
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+use crate::audio;
+use crate::correlation;
+use crate::envelope::Envelope;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export;
+use crate::export::Export;
+use crate::filter::{Filter, FilterKind};
+use crate::functions;
+#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+use crate::midi;
+use crate::note;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::plot_image::PlotLabels;
+use crate::saturation::{Saturation, SaturationKind};
+use crate::sketch::SpectralSketch;
+use crate::spectrogram::{self, Spectrogram};
+use crate::spectrum::{self, SpectrumNorm};
+use crate::undo::UndoStack;
+use crate::util::{Cache, PlotData, RightChannel, SizeBytes, Stats};
+use crate::window::WindowFunction;
+use std::collections::BTreeSet;
+use wavegen::{dc_bias, sawtooth, sine, square, PeriodicFunction, Waveform};
 
+/// Default ratio of sample rate to the highest frequency shown in the spectrum.
 const FMAX_SCALE: f64 = 2.56;
 
-static FFT_PLANNER: once_cell::sync::Lazy<Mutex<FftPlanner<f64>>> =
-    once_cell::sync::Lazy::new(|| Mutex::new(FftPlanner::new()));
+/// Common audio sample rates, offered next to the sample rate setting.
+const SAMPLE_RATE_PRESETS: [f64; 6] = [8000.0, 16000.0, 44100.0, 48000.0, 96000.0, 192000.0];
+
+/// FFT sizes offered in the settings, besides picking one automatically.
+const FFT_SIZES: [usize; 13] = [
+    1 << 8,
+    1 << 9,
+    1 << 10,
+    1 << 11,
+    1 << 12,
+    1 << 13,
+    1 << 14,
+    1 << 15,
+    1 << 16,
+    1 << 17,
+    1 << 18,
+    1 << 19,
+    1 << 20,
+];
+
+/// Zero-padding factors offered in the settings.
+const ZERO_PAD_FACTORS: [usize; 4] = [1, 2, 4, 8];
+
+/// Keys adding the first [`ComponentKind`]s in the palette.
+const SHORTCUTS: [egui::Key; 4] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+];
+
+/// Bit depth the "Quantize to" setting starts at.
+const DEFAULT_BIT_DEPTH: u32 = 8;
+
+/// Peak threshold of the linear magnitude spectrum, by default.
+const DEFAULT_PEAK_THRESHOLD: f64 = 0.1;
+
+/// Peak threshold of the linear power spectral density, by default, in power per Hz.
+const DEFAULT_PSD_PEAK_THRESHOLD: f64 = 1e-6;
+
+/// Number of points the "Limit plotted points" setting starts at.
+const DEFAULT_PLOT_POINTS: usize = 2000;
+
+/// Recommended largest number of samples, and the default limit above which computing the plot
+/// data has to be confirmed.
+///
+/// Generating the waveform and its spectrum takes up to about a second beyond this many samples,
+/// during which the UI is frozen.
+const RECOMMENDED_MAX_SAMPLES: usize = 1 << 20;
+
+/// Decimal places that measured values and parameters are shown with, by default.
+const DEFAULT_DISPLAY_PRECISION: usize = 3;
+
+/// Color of the reference signal, when comparing against one.
+const REFERENCE_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 140, 0);
+
+/// How many times denser than the samples the continuous waveform is computed, at most.
+const CONTINUOUS_OVERSAMPLING: usize = 16;
+
+/// Limit on the points of the continuous waveform. It is oversampled less for long signals.
+const MAX_CONTINUOUS_POINTS: usize = 1 << 16;
+
+/// Number of segments the filter response is drawn with.
+const FILTER_RESPONSE_POINTS: u32 = 512;
+
+/// Color of the right channel of a stereo signal on the plots.
+const RIGHT_CHANNEL_COLOR: egui::Color32 = egui::Color32::from_rgb(200, 80, 200);
+
+/// Color of the max hold trace on the spectrum plot.
+const MAX_HOLD_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 60, 60);
+
+/// Color of the measurement markers on the plots.
+const MARKER_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 170, 170);
+
+/// Color of the lines showing where the pointer is on the other plots.
+const LINKED_HOVER_COLOR: egui::Color32 = egui::Color32::from_gray(150);
+
+/// Most whole periods of the hovered frequency marked on the waveform plot.
+const MAX_PERIOD_LINES: usize = 32;
+
+/// Maximum number of components listed in the spectrum hover tooltip.
+const MAX_CONTRIBUTIONS: usize = 10;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct Main {
+    /// Version of the persisted state, see [`STATE_VERSION`]. State from before versioning was
+    /// introduced reads as version 0.
+    #[serde(default)]
+    version: u32,
     sample_rate: f64,
-    n_samples: u16,
+    n_samples: usize,
+    /// Computing the plot data for more samples than this has to be confirmed first.
+    max_samples: usize,
+    /// Number of samples above `max_samples` that the user agreed to compute.
+    #[serde(skip)]
+    confirmed_samples: Option<usize>,
+    /// Only every `decimation`-th sample is kept, lowering the sample rate by that factor.
+    decimation: usize,
     components: Vec<ComponentWrapper>,
+    /// Groups whose components are left out of the summed waveform.
+    disabled_groups: BTreeSet<String>,
+    master_gain: f64,
+    auto_normalize: bool,
+    /// Nonlinearity applied to the summed waveform, if set.
+    saturation: Option<Saturation>,
+    /// Filter applied to the summed waveform, after the saturation, if set.
+    filter: Option<Filter>,
+    /// Samples are quantized to this many bits before the spectrum is computed, if set.
+    bit_depth: Option<u32>,
+    /// Synthesize square and sawtooth waves from their harmonics below the Nyquist frequency.
+    band_limited: bool,
+    fmax_scale: f64,
+    /// Length of the FFT, or `None` for the next power of two at or above the number of samples.
+    fft_size: Option<usize>,
+    zero_pad_factor: usize,
+    window: WindowFunction,
+    spectrum_norm: SpectrumNorm,
+    spectrum_db: bool,
+    spectrum_psd: bool,
+    /// Show negative frequencies in the spectrum as well.
+    two_sided_spectrum: bool,
+    peak_threshold: f64,
+    spectrum_smoothing: usize,
+    /// Index of the component used as the fundamental for THD, or `None` for the first one.
+    thd_fundamental: Option<usize>,
+    show_phase: bool,
+    unwrap_phase: bool,
+    show_spectrogram: bool,
+    spectrogram_window_size: usize,
+    spectrogram_overlap: f64,
+    spectrum_log_freq: bool,
+    /// Whether the waveform is synthesized from `spectral_sketch` instead of the components.
+    sketch_spectrum: bool,
+    spectral_sketch: SpectralSketch,
+    phase_unit: PhaseUnit,
+    amplitude_unit: AmplitudeUnit,
+    /// Decimal places of the parameters, readouts and the report.
+    display_precision: usize,
+    /// Pitch of A4 that note names entered on the components are tuned to, in Hz.
+    reference_a: f64,
+    show_individual_waveforms: bool,
+    /// Draw the continuous signal the samples are taken from, with the samples as dots.
+    continuous_waveform: bool,
+    /// Mark the samples on the waveform line.
+    show_sample_points: bool,
+    /// Waveforms are reduced to at most this many points before drawing them, if set.
+    plot_point_limit: Option<usize>,
+    waveform_title: String,
+    waveform_x_label: String,
+    waveform_y_label: String,
+    spectrum_title: String,
+    spectrum_x_label: String,
+    spectrum_y_label: String,
+    theme: Theme,
+    /// Look of the summed waveform's line.
+    waveform_line: LineSettings,
+    /// Look of the spectrum's line.
+    spectrum_line: LineSettings,
+    waveform_axes: AxisSettings,
+    spectrum_axes: AxisSettings,
+    show_perf_panel: bool,
+    /// Drop the plot data while the window isn't focused, to free its memory. Native only, as
+    /// the web backend doesn't tell when the page loses focus.
+    #[cfg(not(target_arch = "wasm32"))]
+    free_when_unfocused: bool,
+    show_component_table: bool,
+    /// Presets saved within the app, restorable from the "Presets" menu.
+    presets: Vec<NamedPreset>,
+    /// Maximum number of frames kept in the frame time history.
+    history_size: usize,
+    /// Maximum age of the frames kept in the frame time history, in seconds.
+    max_history_age: f32,
+
+    #[serde(skip)]
+    error: Option<String>,
+
+    /// Whether the "clear all components" confirmation is shown.
+    #[serde(skip)]
+    confirm_clear: bool,
+
+    /// Whether the "reset app" confirmation is shown.
+    #[serde(skip)]
+    confirm_reset: bool,
+
+    /// Name entered for the next preset saved in the "Presets" menu.
+    #[serde(skip)]
+    new_preset_name: String,
+
+    /// Indices of the components selected for batch operations.
+    #[serde(skip)]
+    selected: BTreeSet<usize>,
+
+    /// Factor the "Scale" batch operation multiplies amplitudes by.
+    #[serde(skip)]
+    batch_scale: f64,
+
+    /// Index of the component whose spectrum is shown on its own, instead of the sum's.
+    #[serde(skip)]
+    isolated: Option<usize>,
+
+    #[serde(skip)]
+    isolated_spectrum: Cache<IsolatedSpectrum>,
+
+    /// The pair of components shown correlated or convolved, if that section is shown.
+    #[serde(skip)]
+    correlation: Option<CorrelationSetup>,
+    #[serde(skip)]
+    correlation_plot: Cache<CorrelationPlot>,
+
+    /// Index of the component card the arrow keys, Enter and Tab act on.
+    #[serde(skip)]
+    focused_card: Option<usize>,
+
+    /// Snapshot of the plot data that the current signal is compared against.
+    #[serde(skip)]
+    reference: Option<PlotData>,
+
+    /// Overlay the largest spectrum seen on the spectrum plot.
+    max_hold: bool,
+    #[serde(skip)]
+    held_spectrum: spectrum::MaxHold,
+
+    /// Only components whose name contains this, ignoring case, are listed.
+    #[serde(skip)]
+    component_filter: String,
+
+    #[serde(skip)]
+    waveform_markers: Markers,
+    #[serde(skip)]
+    spectrum_markers: Markers,
+    /// Where the pointer was on the plots in the last frame.
+    #[serde(skip)]
+    linked_hover: Option<LinkedHover>,
+
+    /// Settings of the "harmonic series" dialog, while it is shown.
+    #[serde(skip)]
+    harmonic_series: Option<HarmonicSeries>,
+
+    /// Export requested from the menu, carried out once the data it needs is available.
+    #[serde(skip)]
+    pending_export: Option<Export>,
+    /// Like `pending_export`, for the report.
+    #[serde(skip)]
+    pending_report: bool,
+
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    playback: Option<audio::Playback>,
+
+    /// MIDI input port that notes are played from, if connected.
+    #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+    #[serde(skip)]
+    midi_input: Option<midi::Input>,
 
     #[serde(skip)]
     history: History,
 
+    #[serde(skip)]
+    waveform_view: PlotView,
+
+    #[serde(skip)]
+    spectrum_view: PlotView,
+
+    /// Samples loaded from a WAV file, analyzed instead of the components while present.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    imported: Option<Vec<f64>>,
+
+    #[serde(skip)]
+    undo: UndoStack<Vec<ComponentWrapper>>,
+
     #[serde(skip)]
     plot_data_cache: Cache<PlotData>,
 }
@@ -26,10 +305,96 @@ pub struct Main {
 impl Default for Main {
     fn default() -> Self {
         Self {
+            version: STATE_VERSION,
             sample_rate: 3000.0,
             n_samples: 1000,
+            max_samples: RECOMMENDED_MAX_SAMPLES,
+            confirmed_samples: None,
+            decimation: 1,
             components: vec![],
-            history: History::new(),
+            disabled_groups: BTreeSet::new(),
+            master_gain: 1.0,
+            auto_normalize: false,
+            saturation: None,
+            filter: None,
+            bit_depth: None,
+            band_limited: false,
+            fmax_scale: FMAX_SCALE,
+            fft_size: None,
+            zero_pad_factor: 1,
+            window: WindowFunction::default(),
+            spectrum_norm: SpectrumNorm::default(),
+            spectrum_db: false,
+            spectrum_psd: false,
+            two_sided_spectrum: false,
+            peak_threshold: DEFAULT_PEAK_THRESHOLD,
+            spectrum_smoothing: 1,
+            thd_fundamental: None,
+            show_phase: false,
+            unwrap_phase: false,
+            show_spectrogram: false,
+            spectrogram_window_size: 256,
+            spectrogram_overlap: 0.5,
+            spectrum_log_freq: false,
+            sketch_spectrum: false,
+            spectral_sketch: SpectralSketch::default(),
+            phase_unit: PhaseUnit::default(),
+            amplitude_unit: AmplitudeUnit::default(),
+            display_precision: DEFAULT_DISPLAY_PRECISION,
+            reference_a: note::DEFAULT_REFERENCE_A,
+            show_individual_waveforms: false,
+            continuous_waveform: false,
+            show_sample_points: false,
+            plot_point_limit: None,
+            waveform_title: "Plot".to_owned(),
+            waveform_x_label: "Time [s]".to_owned(),
+            waveform_y_label: "Amplitude".to_owned(),
+            spectrum_title: "Spectrum".to_owned(),
+            spectrum_x_label: "Frequency [Hz]".to_owned(),
+            spectrum_y_label: "Magnitude".to_owned(),
+            theme: Theme::default(),
+            waveform_line: LineSettings::default(),
+            spectrum_line: LineSettings::default(),
+            waveform_axes: AxisSettings::default(),
+            spectrum_axes: AxisSettings::default(),
+            show_perf_panel: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            free_when_unfocused: false,
+            show_component_table: false,
+            presets: vec![],
+            history_size: HISTORY_SIZE,
+            max_history_age: MAX_HISTORY_AGE,
+            error: None,
+            confirm_clear: false,
+            confirm_reset: false,
+            new_preset_name: String::new(),
+            selected: BTreeSet::new(),
+            batch_scale: 0.5,
+            isolated: None,
+            isolated_spectrum: Cache::default(),
+            correlation: None,
+            correlation_plot: Cache::default(),
+            focused_card: None,
+            reference: None,
+            max_hold: false,
+            held_spectrum: spectrum::MaxHold::default(),
+            component_filter: String::new(),
+            waveform_markers: Markers::default(),
+            spectrum_markers: Markers::default(),
+            linked_hover: None,
+            harmonic_series: None,
+            pending_export: None,
+            pending_report: false,
+            #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+            playback: None,
+            #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+            midi_input: None,
+            history: History::new(HISTORY_SIZE, MAX_HISTORY_AGE),
+            waveform_view: PlotView::default(),
+            spectrum_view: PlotView::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            imported: None,
+            undo: UndoStack::new(),
             plot_data_cache: Cache::default(),
         }
     }
@@ -45,13 +410,53 @@ impl Main {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            let mut app = storage
+                .get_string(eframe::APP_KEY)
+                .and_then(|ron| Self::restore(&ron))
+                .unwrap_or_default();
+            app.history = History::new(app.history_size, app.max_history_age);
+            cc.egui_ctx
+                .set_visuals(app.theme.visuals(cc.integration_info.system_theme));
+            return app;
         }
 
         Self::default()
     }
+
+    /// Restores the app state persisted as `ron`, upgrading it from older versions of the app.
+    ///
+    /// Returns `None` if it can't be parsed, or comes from a newer version of the app.
+    fn restore(ron: &str) -> Option<Self> {
+        let mut app: Self = ron::from_str(ron).ok()?;
+        if app.version > STATE_VERSION {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        for migrate in &MIGRATIONS[app.version as usize..] {
+            migrate(&mut app);
+        }
+        app.version = STATE_VERSION;
+
+        Some(app)
+    }
 }
 
+/// Current version of the persisted app state.
+///
+/// New fields are filled in by `#[serde(default)]`, and renamed ones can be read with
+/// `#[serde(alias)]`. Changes to what stored values mean need a new version, with a step in
+/// [`MIGRATIONS`] that converts the old values.
+const STATE_VERSION: u32 = 1;
+
+/// Steps upgrading the persisted app state by one version; the `i`-th one upgrades from
+/// version `i`.
+const MIGRATIONS: [fn(&mut Main); STATE_VERSION as usize] = [
+    // Unversioned state. The oldest of it stored `n_samples` as a `u16`, which reads as a
+    // `usize` unchanged, and the fields added since then get their defaults, so there is
+    // nothing to convert.
+    |_| {},
+];
+
 impl eframe::App for Main {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
@@ -61,24 +466,427 @@ impl eframe::App for Main {
     #[allow(clippy::too_many_lines)]
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         let Self {
+            version: _,
             sample_rate,
             n_samples,
+            max_samples,
+            confirmed_samples,
+            decimation,
             components,
+            disabled_groups,
+            master_gain,
+            auto_normalize,
+            saturation,
+            filter,
+            bit_depth,
+            band_limited,
+            fmax_scale,
+            fft_size,
+            zero_pad_factor,
+            window,
+            spectrum_norm,
+            spectrum_db,
+            spectrum_psd,
+            two_sided_spectrum,
+            peak_threshold,
+            spectrum_smoothing,
+            thd_fundamental,
+            show_phase,
+            unwrap_phase,
+            show_spectrogram,
+            spectrogram_window_size,
+            spectrogram_overlap,
+            spectrum_log_freq,
+            sketch_spectrum,
+            spectral_sketch,
+            phase_unit,
+            amplitude_unit,
+            display_precision,
+            reference_a,
+            show_individual_waveforms,
+            continuous_waveform,
+            show_sample_points,
+            plot_point_limit,
+            waveform_title,
+            waveform_x_label,
+            waveform_y_label,
+            spectrum_title,
+            spectrum_x_label,
+            spectrum_y_label,
+            theme,
+            waveform_line,
+            spectrum_line,
+            waveform_axes,
+            spectrum_axes,
+            show_perf_panel,
+            #[cfg(not(target_arch = "wasm32"))]
+            free_when_unfocused,
+            show_component_table,
+            presets,
+            history_size,
+            max_history_age,
+            error,
+            confirm_clear,
+            confirm_reset,
+            new_preset_name,
+            selected,
+            batch_scale,
+            isolated,
+            isolated_spectrum,
+            correlation,
+            correlation_plot,
+            focused_card,
+            reference,
+            max_hold,
+            held_spectrum,
+            component_filter,
+            waveform_markers,
+            spectrum_markers,
+            linked_hover,
+            harmonic_series,
+            pending_export,
+            pending_report,
+            #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+            playback,
+            #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+            midi_input,
             history,
+            waveform_view,
+            spectrum_view,
+            #[cfg(not(target_arch = "wasm32"))]
+            imported,
+            undo,
             plot_data_cache,
         } = self;
 
         history.on_new_frame(ctx.input().time, frame.info().cpu_usage);
+        // Also picks up changes of the system theme.
+        let visuals = theme.visuals(frame.info().system_theme);
+        if ctx.style().visuals.dark_mode != visuals.dark_mode {
+            ctx.set_visuals(visuals);
+        }
+        // Made available to the widgets deep in the component cards, like the visuals.
+        ctx.data()
+            .insert_temp(egui::Id::new(PRECISION_KEY), *display_precision);
+        let precision = *display_precision;
+        #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+        if let Some(e) = playback.as_ref().and_then(audio::Playback::error) {
+            *error = Some(format!("Playback stopped: {e}"));
+            *playback = None;
+        }
+        #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+        if let Some(input) = midi_input {
+            for event in input.events() {
+                if play_midi_event(components, event, *reference_a) {
+                    plot_data_cache.invalidate();
+                }
+            }
+        }
+        // Tab goes into the focused card, instead of to the first widget of the window.
+        let nothing_focused = ctx.memory().focus().is_none();
+        let tab_into_card =
+            nothing_focused && focused_card.is_some() && ctx.input().key_pressed(egui::Key::Tab);
+        let mut undo_requested = false;
+        let mut redo_requested = false;
+        let mut reset = false;
+
+        #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+        let mut play = false;
 
-        #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
-                    if ui.button("Quit").clicked() {
-                        frame.close();
+                    // The web version has no file to load presets back from; the "Presets" menu
+                    // keeps them in the browser instead.
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Save preset").clicked() {
+                        let preset = Preset::new(*sample_rate, *n_samples, components.clone());
+                        if let Err(e) = preset.save() {
+                            *error = Some(format!("Could not save preset: {e}"));
+                        }
+                        ui.close_menu();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Load preset").clicked() {
+                        match Preset::load() {
+                            Ok(Some(preset)) => {
+                                *sample_rate = preset.sample_rate;
+                                *n_samples = preset.n_samples;
+                                *components = preset.components;
+                                selected.clear();
+                                *isolated = None;
+                                *focused_card = None;
+                                *thd_fundamental = None;
+                                *correlation = None;
+                                plot_data_cache.invalidate();
+                            }
+                            Ok(None) => {}
+                            Err(e) => *error = Some(format!("Could not load preset: {e}")),
+                        }
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Import WAV").clicked() {
+                        match export::load_wav() {
+                            Ok(Some((samples, rate))) => {
+                                *imported = Some(samples);
+                                *sample_rate = rate;
+                                plot_data_cache.invalidate();
+                            }
+                            Ok(None) => {}
+                            Err(e) => *error = Some(format!("Could not import WAV: {e}")),
+                        }
+                        ui.close_menu();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui
+                        .add_enabled(imported.is_some(), egui::Button::new("Close imported WAV"))
+                        .clicked()
+                    {
+                        *imported = None;
+                        plot_data_cache.invalidate();
+                        ui.close_menu();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Export WAV").clicked() {
+                        *pending_export = Some(Export::Wav);
+                        ui.close_menu();
+                    }
+                    if ui.button("Export waveform CSV").clicked() {
+                        *pending_export = Some(Export::WaveformCsv);
+                        ui.close_menu();
+                    }
+                    if ui.button("Export spectrum CSV").clicked() {
+                        *pending_export = Some(Export::SpectrumCsv);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("Copy signal as code")
+                        .on_hover_text(
+                            "Copy the audible components as a wavegen waveform, to paste into \
+                             Rust code.\nWhat can't be written as code, like envelopes and \
+                             delays, is noted in comments",
+                        )
+                        .clicked()
+                    {
+                        let any_solo = components.iter().any(|c| c.solo);
+                        let audible = components.iter().filter(|c| {
+                            c.enabled && c.active && c.is_audible(any_solo, disabled_groups)
+                        });
+                        ui.output().copied_text = signal_code(*sample_rate, *band_limited, audible);
+                        ui.close_menu();
+                    }
+                    let report_label = if cfg!(target_arch = "wasm32") {
+                        "Copy report"
+                    } else {
+                        "Export report"
+                    };
+                    if ui
+                        .button(report_label)
+                        .on_hover_text(
+                            "A Markdown summary of the setup, the components and the signal \
+                             statistics",
+                        )
+                        .clicked()
+                    {
+                        *pending_report = true;
+                        ui.close_menu();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Export waveform PNG").clicked() {
+                        *pending_export = Some(Export::WaveformPng(PlotLabels {
+                            title: waveform_title.clone(),
+                            x: waveform_x_label.clone(),
+                            y: waveform_y_label.clone(),
+                        }));
+                        ui.close_menu();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui.button("Export spectrum PNG").clicked() {
+                        *pending_export = Some(Export::SpectrumPng(PlotLabels {
+                            title: spectrum_title.clone(),
+                            x: spectrum_x_label.clone(),
+                            y: normalized_label(spectrum_y_label, *spectrum_norm, *spectrum_psd),
+                        }));
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Reset app").clicked() {
+                        *confirm_reset = true;
+                        ui.close_menu();
+                    }
+                    #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
+                    {
+                        ui.separator();
+                        if ui.button("Quit").clicked() {
+                            frame.close();
+                        }
                     }
                 });
+
+                ui.menu_button("Edit", |ui| {
+                    if ui
+                        .add_enabled(
+                            undo.can_undo(),
+                            egui::Button::new("Undo").shortcut_text("Ctrl+Z"),
+                        )
+                        .clicked()
+                    {
+                        undo_requested = true;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            undo.can_redo(),
+                            egui::Button::new("Redo").shortcut_text("Ctrl+Y"),
+                        )
+                        .clicked()
+                    {
+                        redo_requested = true;
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Presets", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(new_preset_name)
+                                .hint_text("Name")
+                                .desired_width(120.0),
+                        );
+                        let name = new_preset_name.trim();
+                        if ui
+                            .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                            .on_hover_text(
+                                "Save the current signal, replacing any preset of this name",
+                            )
+                            .clicked()
+                        {
+                            let preset = Preset::new(*sample_rate, *n_samples, components.clone());
+                            match presets.iter_mut().find(|p| p.name == name) {
+                                Some(existing) => existing.preset = preset,
+                                None => presets.push(NamedPreset {
+                                    name: name.to_owned(),
+                                    preset,
+                                }),
+                            }
+                            new_preset_name.clear();
+                        }
+                    });
+                    if !presets.is_empty() {
+                        ui.separator();
+                    }
+                    let mut remove = None;
+                    for (i, NamedPreset { name, preset }) in presets.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button(name)
+                                .on_hover_text("Restore this preset")
+                                .clicked()
+                            {
+                                *sample_rate = preset.sample_rate;
+                                *n_samples = preset.n_samples;
+                                *components = preset.components.clone();
+                                selected.clear();
+                                *isolated = None;
+                                *focused_card = None;
+                                *thd_fundamental = None;
+                                *correlation = None;
+                                plot_data_cache.invalidate();
+                                ui.close_menu();
+                            }
+                            if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = remove {
+                        presets.remove(i);
+                    }
+                });
+                ui.menu_button("Examples", |ui| {
+                    for example in EXAMPLES {
+                        if ui
+                            .button(example.name)
+                            .on_hover_text(example.description)
+                            .clicked()
+                        {
+                            let preset = example.preset();
+                            *sample_rate = preset.sample_rate;
+                            *n_samples = preset.n_samples;
+                            *components = preset.components;
+                            selected.clear();
+                            *isolated = None;
+                            *focused_card = None;
+                            *thd_fundamental = None;
+                            *correlation = None;
+                            plot_data_cache.invalidate();
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    ui.label("Theme");
+                    for t in Theme::ALL {
+                        if ui.selectable_value(theme, t, t.name()).clicked() {
+                            ctx.set_visuals(t.visuals(frame.info().system_theme));
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+                {
+                    ui.separator();
+                    if playback.is_some() {
+                        if ui.button("⏹ Stop").clicked() {
+                            *playback = None;
+                        }
+                    } else if ui.button("▶ Play").clicked() {
+                        play = true;
+                    }
+                }
+
+                #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+                ui.menu_button("🎹 MIDI", |ui| {
+                    if let Some(input) = midi_input {
+                        ui.label(format!("Playing from {}", input.port()));
+                        if ui.button("Disconnect").clicked() {
+                            *midi_input = None;
+                            ui.close_menu();
+                        }
+                        return;
+                    }
+                    match midi::ports() {
+                        Ok(ports) if ports.is_empty() => {
+                            ui.label("No MIDI input ports found");
+                        }
+                        Ok(ports) => {
+                            for port in ports {
+                                if ui.button(&port).clicked() {
+                                    match midi::Input::connect(&port, ctx.clone()) {
+                                        Ok(input) => *midi_input = Some(input),
+                                        Err(e) => {
+                                            *error =
+                                                Some(format!("Could not connect to {port}: {e}"));
+                                        }
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            ui.label(format!("Could not list MIDI ports: {e}"));
+                        }
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Notes played on the connected port add sine components, with the velocity \
+                     as amplitude. Released notes are muted.",
+                );
             });
         });
 
@@ -106,221 +914,4253 @@ impl eframe::App for Main {
                         .small(),
                 )
                 .on_hover_ui(|ui| history.show_plot(ui));
+                ui.separator();
+                let plot_data = plot_data_cache.size_bytes()
+                    + reference.size_bytes()
+                    + isolated_spectrum.size_bytes()
+                    + correlation_plot.size_bytes()
+                    + held_spectrum.size_bytes();
+                ui.label(
+                    egui::RichText::new(format!("Plot data: {}", format_bytes(plot_data))).small(),
+                )
+                .on_hover_text(
+                    "Estimated memory held by the computed plots, including the reference, the \
+                     isolated spectrum, the correlation and the max hold",
+                );
             });
         });
 
+        if *show_perf_panel {
+            egui::TopBottomPanel::bottom("perf_panel").show(ctx, |ui| {
+                egui::CollapsingHeader::new("Performance")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let size_changed = committed(
+                                ui.add(
+                                    egui::DragValue::new(history_size)
+                                        .clamp_range(2..=100_000)
+                                        .prefix("History size: ")
+                                        .suffix(" frames"),
+                                ),
+                                *history_size,
+                            )
+                            .changed();
+                            let age_changed = committed(
+                                ui.add(
+                                    egui::DragValue::new(max_history_age)
+                                        .clamp_range(0.1..=60.0)
+                                        .speed(0.1)
+                                        .prefix("Max age: ")
+                                        .suffix(" s"),
+                                ),
+                                *max_history_age,
+                            )
+                            .changed();
+                            if size_changed || age_changed {
+                                *history = History::new(*history_size, *max_history_age);
+                            }
+                        });
+                        #[cfg(not(target_arch = "wasm32"))]
+                        ui.checkbox(free_when_unfocused, "Free plot data while unfocused")
+                            .on_hover_text(
+                                "Drop the computed plots when the window loses focus, and \
+                                 compute them again when it gets it back",
+                            );
+                        // The plot keeps its aspect ratio, so limit its width to keep the panel low.
+                        ui.scope(|ui| {
+                            ui.set_max_width(600.0);
+                            history.show_plot(ui);
+                        });
+                    });
+            });
+        }
+
         egui::SidePanel::left("left_panel").show(ctx, |ui| {
-            ui.heading("Add new component");
-            if ui.button("Sine").clicked() {
-                components.push(ComponentWrapper {
-                    inner: Component::Sine {
-                        frequency: 100.0,
-                        amplitude: 1.0,
-                        phase: 0.0,
-                    },
-                    name: "Sine".to_string(),
-                    enabled: true,
-                });
-                plot_data_cache.invalidate();
-            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.heading("Add new component");
+                egui::ScrollArea::vertical()
+                    .id_source("palette")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("palette_grid")
+                            .num_columns(2)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (i, kind) in ComponentKind::ALL.iter().enumerate() {
+                                    ui.label(kind.name()).on_hover_text(kind.description());
+                                    let mut button = ui.button("Add");
+                                    if i < SHORTCUTS.len() {
+                                        button =
+                                            button.on_hover_text(format!("Shortcut: {}", i + 1));
+                                    }
+                                    if button.clicked() {
+                                        components.push(ComponentWrapper::new(
+                                            kind.name(),
+                                            kind.default_component(),
+                                        ));
+                                        plot_data_cache.invalidate();
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
 
-            if ui.button("Square").clicked() {
-                components.push(ComponentWrapper {
-                    inner: Component::Square {
-                        frequency: 100.0,
-                        amplitude: 1.0,
-                        phase: 0.0,
-                    },
-                    name: "Square".to_string(),
-                    enabled: true,
-                });
-                plot_data_cache.invalidate();
-            }
+                if ui.button("Harmonic series").clicked() {
+                    *harmonic_series = Some(HarmonicSeries::default());
+                }
 
-            if ui.button("Sawtooth").clicked() {
-                components.push(ComponentWrapper {
-                    inner: Component::Sawtooth {
-                        frequency: 100.0,
-                        amplitude: 1.0,
-                        phase: 0.0,
-                    },
-                    name: "Sawtooth".to_string(),
-                    enabled: true,
-                });
-                plot_data_cache.invalidate();
-            }
+                ui.add_space(8.0);
+                if ui
+                    .add_enabled(!components.is_empty(), egui::Button::new("🗑 Clear all"))
+                    .clicked()
+                {
+                    *confirm_clear = true;
+                }
 
-            ui.separator();
+                ui.separator();
 
-            ui.heading("Settings");
-            if ui
-                .add(
-                    egui::DragValue::new(sample_rate)
-                        .clamp_range(f64::MIN_POSITIVE..=f64::MAX)
-                        .prefix("Sample rate: ")
-                        .suffix(" Hz"),
+                ui.heading("Settings");
+                // What the sampling settings amount to, as of the start of the frame.
+                #[allow(clippy::cast_precision_loss)]
+                let analysis_rate = *sample_rate / *decimation as f64;
+                let nyquist = analysis_rate / 2.0;
+                let analyzed = (*n_samples + *decimation - 1) / *decimation;
+                let transform_len = fft_size.unwrap_or_else(|| analyzed.next_power_of_two());
+                #[allow(clippy::cast_precision_loss)]
+                let resolution = analysis_rate / transform_len as f64;
+                #[allow(clippy::cast_precision_loss)]
+                let duration = *n_samples as f64 / *sample_rate;
+                ui.horizontal(|ui| {
+                    if committed(
+                        ui.add(
+                            egui::DragValue::new(sample_rate)
+                                .clamp_range(f64::MIN_POSITIVE..=f64::MAX)
+                                .prefix("Sample rate: ")
+                                .suffix(" Hz"),
+                        ),
+                        *sample_rate,
+                    )
+                    .on_hover_text(format!(
+                        "Samples per second. Only frequencies below half of it, the Nyquist \
+                         frequency, can be told apart;\nhigher ones alias to lower ones.\n\
+                         Nyquist frequency: {nyquist:.precision$} Hz"
+                    ))
+                    .changed()
+                    {
+                        plot_data_cache.invalidate();
+                    }
+                    egui::ComboBox::from_id_source("sample_rate_presets")
+                        .selected_text("Presets")
+                        .show_ui(ui, |ui| {
+                            for rate in SAMPLE_RATE_PRESETS {
+                                if ui
+                                    .selectable_label(*sample_rate == rate, format!("{rate} Hz"))
+                                    .clicked()
+                                {
+                                    *sample_rate = rate;
+                                    plot_data_cache.invalidate();
+                                }
+                            }
+                        });
+                    ui.weak(format!("Nyquist: {nyquist:.precision$} Hz"));
+                });
+                ui.horizontal(|ui| {
+                    if committed(
+                        ui.add(
+                            egui::DragValue::new(n_samples)
+                                .clamp_range(usize::MIN..=usize::MAX)
+                                .prefix("N Samples: "),
+                        ),
+                        *n_samples,
+                    )
+                    .on_hover_text(format!(
+                        "Length of the sample window: {duration:.precision$} s.\n\
+                         The spectrum's bins are sample rate / FFT size apart, so more samples \
+                         (and a larger FFT) resolve closer frequencies.\n\
+                         Resolution: {analysis_rate:.precision$} Hz / {transform_len} = \
+                         {resolution:.precision$} Hz"
+                    ))
+                    .changed()
+                    {
+                        plot_data_cache.invalidate();
+                    }
+                    ui.weak(format!("Resolution: {resolution:.precision$} Hz"));
+                });
+                committed(
+                    ui.add(
+                        egui::DragValue::new(max_samples)
+                            .clamp_range(1..=usize::MAX)
+                            .prefix("Ask above: ")
+                            .suffix(" samples"),
+                    ),
+                    *max_samples,
+                )
+                .on_hover_text(format!(
+                    "Ask before computing more samples than this, which may freeze the app \
+                     for a while.\nRecommended: at most {RECOMMENDED_MAX_SAMPLES}"
+                ));
+                ui.horizontal(|ui| {
+                    if committed(
+                        ui.add(
+                            egui::DragValue::new(decimation)
+                                .clamp_range(1..=1000)
+                                .prefix("Decimation: "),
+                        ),
+                        *decimation,
+                    )
+                    .on_hover_text(
+                        "Keep only every N-th sample, lowering the sample rate,\n\
+                         to see what aliases",
+                    )
+                    .changed()
+                    {
+                        plot_data_cache.invalidate();
+                    }
+                    if *decimation > 1 {
+                        #[allow(clippy::cast_precision_loss)]
+                        let rate = *sample_rate / *decimation as f64;
+                        ui.weak(format!("→ {rate} Hz"));
+                    }
+                });
+                if committed(
+                    ui.add(
+                        egui::DragValue::new(master_gain)
+                            .clamp_range(0.0..=f64::MAX)
+                            .speed(0.01)
+                            .prefix("Master gain: "),
+                    ),
+                    *master_gain,
                 )
                 .changed()
-            {
-                plot_data_cache.invalidate();
-            }
-            if ui
-                .add(
-                    egui::DragValue::new(n_samples)
-                        .clamp_range(usize::MIN..=usize::MAX)
-                        .prefix("N Samples: "),
+                {
+                    plot_data_cache.invalidate();
+                }
+                if ui
+                    .checkbox(auto_normalize, "Auto-normalize")
+                    .on_hover_text("Scale the waveform so that its peak is at ±1")
+                    .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                ui.horizontal(|ui| {
+                    let mut saturate = saturation.is_some();
+                    let mut settings = saturation.unwrap_or_default();
+                    let previous_kind = settings.kind;
+                    let mut changed = ui
+                        .checkbox(&mut saturate, "Saturate")
+                        .on_hover_text(
+                            "Amplify the summed waveform by the drive, then clip it at the \
+                             threshold,\nto show the harmonics distortion generates",
+                        )
+                        .changed();
+                    ui.add_enabled_ui(saturate, |ui| {
+                        egui::ComboBox::from_id_source("saturation_kind")
+                            .selected_text(settings.kind.name())
+                            .show_ui(ui, |ui| {
+                                for kind in SaturationKind::ALL {
+                                    ui.selectable_value(&mut settings.kind, kind, kind.name());
+                                }
+                            });
+                        changed |= settings.kind != previous_kind;
+                        changed |= committed(
+                            ui.add(
+                                egui::DragValue::new(&mut settings.drive)
+                                    .clamp_range(0.0..=f64::MAX)
+                                    .speed(0.01)
+                                    .prefix("Drive: "),
+                            ),
+                            settings.drive,
+                        )
+                        .changed();
+                        changed |= committed(
+                            ui.add(
+                                egui::DragValue::new(&mut settings.threshold)
+                                    .clamp_range(0.001..=f64::MAX)
+                                    .speed(0.01)
+                                    .prefix("Threshold: "),
+                            ),
+                            settings.threshold,
+                        )
+                        .changed();
+                    });
+                    if changed {
+                        *saturation = saturate.then_some(settings);
+                        plot_data_cache.invalidate();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut enabled = filter.is_some();
+                    let mut settings = filter.unwrap_or_default();
+                    let previous_kind = settings.kind;
+                    let mut changed = ui
+                        .checkbox(&mut enabled, "Filter")
+                        .on_hover_text(
+                            "Pass the summed waveform through a biquad filter.\n\
+                             Its magnitude response is plotted below the spectrum",
+                        )
+                        .changed();
+                    ui.add_enabled_ui(enabled, |ui| {
+                        egui::ComboBox::from_id_source("filter_kind")
+                            .selected_text(settings.kind.name())
+                            .show_ui(ui, |ui| {
+                                for kind in FilterKind::ALL {
+                                    ui.selectable_value(&mut settings.kind, kind, kind.name());
+                                }
+                            });
+                        changed |= settings.kind != previous_kind;
+                        changed |= committed(
+                            ui.add(
+                                egui::DragValue::new(&mut settings.cutoff)
+                                    .clamp_range(1e-2..=*sample_rate / 2.0)
+                                    .prefix("Cutoff: ")
+                                    .suffix(" Hz"),
+                            ),
+                            settings.cutoff,
+                        )
+                        .changed();
+                        changed |= committed(
+                            ui.add(
+                                egui::DragValue::new(&mut settings.q)
+                                    .clamp_range(0.01..=100.0)
+                                    .speed(0.01)
+                                    .prefix("Q: "),
+                            ),
+                            settings.q,
+                        )
+                        .changed();
+                    });
+                    if changed {
+                        *filter = enabled.then_some(settings);
+                        plot_data_cache.invalidate();
+                    }
+                });
+                if ui
+                    .checkbox(band_limited, "Band-limited square and sawtooth")
+                    .on_hover_text(format!(
+                        "Build square and sawtooth waves from their harmonics below the \
+                         Nyquist frequency,\nso that they don't alias. Up to {} harmonics \
+                         are used, so very low waves are a little rounded off",
+                        functions::MAX_HARMONICS
+                    ))
+                    .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                ui.horizontal(|ui| {
+                    let mut quantize = bit_depth.is_some();
+                    let mut bits = bit_depth.unwrap_or(DEFAULT_BIT_DEPTH);
+                    if ui
+                        .checkbox(&mut quantize, "Quantize to")
+                        .on_hover_text(
+                            "Round each sample to one of 2^bits levels across [-1, 1],\n\
+                             to show the quantization noise in the spectrum",
+                        )
+                        .changed()
+                        | committed(
+                            ui.add_enabled(
+                                quantize,
+                                egui::DragValue::new(&mut bits)
+                                    .clamp_range(1..=32)
+                                    .suffix(" bits"),
+                            ),
+                            bits,
+                        )
+                        .changed()
+                    {
+                        *bit_depth = quantize.then_some(bits);
+                        plot_data_cache.invalidate();
+                    }
+                });
+                if committed(
+                    ui.add(
+                        egui::DragValue::new(fmax_scale)
+                            .clamp_range(1.0..=10.0)
+                            .speed(0.01)
+                            .prefix("Fmax scale: "),
+                    ),
+                    *fmax_scale,
                 )
+                .on_hover_text(format!(
+                    "Ratio of sample rate to the highest frequency shown in the spectrum.\n\
+                     2.0 shows everything up to the Nyquist frequency.\n\
+                     Highest frequency shown: {:.precision$} Hz",
+                    analysis_rate / *fmax_scale
+                ))
                 .changed()
-            {
-                plot_data_cache.invalidate();
-            }
+                {
+                    plot_data_cache.invalidate();
+                }
+                let previous_window = *window;
+                egui::ComboBox::from_label("Window")
+                    .selected_text(window.name())
+                    .show_ui(ui, |ui| {
+                        for w in WindowFunction::ALL {
+                            ui.selectable_value(window, w, w.name());
+                        }
+                    });
+                if *window != previous_window {
+                    plot_data_cache.invalidate();
+                }
+                let previous_fft_size = *fft_size;
+                egui::ComboBox::from_label("FFT size")
+                    .selected_text(match fft_size {
+                        Some(size) => size.to_string(),
+                        None => "Auto".to_owned(),
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(fft_size, None, "Auto");
+                        for size in FFT_SIZES {
+                            ui.selectable_value(fft_size, Some(size), size.to_string());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Length of the FFT. The samples are cut off or padded with zeros to it.\n\
+                         Auto picks the next power of two at or above the number of samples",
+                    );
+                if *fft_size != previous_fft_size {
+                    plot_data_cache.invalidate();
+                }
+                let previous_zero_pad_factor = *zero_pad_factor;
+                egui::ComboBox::from_label("Zero padding")
+                    .selected_text(format!("{zero_pad_factor}×"))
+                    .show_ui(ui, |ui| {
+                        for f in ZERO_PAD_FACTORS {
+                            ui.selectable_value(zero_pad_factor, f, format!("{f}×"));
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Pads the FFT input with zeros. This interpolates the spectrum,\n\
+                         but doesn't add real frequency resolution",
+                    );
+                if *zero_pad_factor != previous_zero_pad_factor {
+                    plot_data_cache.invalidate();
+                }
+                let previous_spectrum_norm = *spectrum_norm;
+                egui::ComboBox::from_label("Normalization")
+                    .selected_text(spectrum_norm.name())
+                    .show_ui(ui, |ui| {
+                        for n in SpectrumNorm::ALL {
+                            ui.selectable_value(spectrum_norm, n, n.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Scaling of the magnitude spectrum. Amplitude makes a sinusoid read its \
+                         amplitude with any window",
+                    );
+                if *spectrum_norm != previous_spectrum_norm {
+                    plot_data_cache.invalidate();
+                }
+                if ui.checkbox(spectrum_db, "Spectrum in dB").changed() {
+                    // The threshold is in the units of the spectrum, so it's converted along.
+                    let decibels = if *spectrum_psd { 10.0 } else { 20.0 };
+                    *peak_threshold = if *spectrum_db {
+                        decibels * peak_threshold.max(f64::MIN_POSITIVE).log10()
+                    } else {
+                        10_f64.powf(*peak_threshold / decibels)
+                    };
+                    // A fixed range in the old units would squash or lose the spectrum.
+                    if let Some(range) = &mut spectrum_axes.y_range {
+                        *range = spectrum_range(*spectrum_db);
+                    }
+                    plot_data_cache.invalidate();
+                }
+                if ui
+                    .checkbox(two_sided_spectrum, "Two-sided spectrum")
+                    .on_hover_text(
+                        "Show negative frequencies too. Each sinusoid then shows up at both \
+                         its positive and negative frequency, with half of its amplitude",
+                    )
+                    .changed()
+                {
+                    plot_data_cache.invalidate();
+                    spectrum_view.reset();
+                }
+                if ui
+                    .checkbox(spectrum_psd, "Power spectral density")
+                    .on_hover_text(
+                        "Show power per Hz instead of magnitude, e.g. for noise analysis",
+                    )
+                    .changed()
+                {
+                    // A magnitude doesn't convert to a density, so the threshold starts over.
+                    let threshold = if *spectrum_psd {
+                        DEFAULT_PSD_PEAK_THRESHOLD
+                    } else {
+                        DEFAULT_PEAK_THRESHOLD
+                    };
+                    *peak_threshold = if *spectrum_db {
+                        let decibels = if *spectrum_psd { 10.0 } else { 20.0 };
+                        decibels * threshold.log10()
+                    } else {
+                        threshold
+                    };
+                    plot_data_cache.invalidate();
+                }
+                if committed(
+                    ui.add(
+                        egui::DragValue::new(peak_threshold)
+                            .speed(if *spectrum_db { 0.5 } else { 0.01 })
+                            .prefix("Peak threshold: ")
+                            .suffix(if *spectrum_db { " dB" } else { "" }),
+                    ),
+                    *peak_threshold,
+                )
+                .on_hover_text(
+                    "Spectrum peaks at or above this value are marked on the plot.\n\
+                     It is in the units of the spectrum, and converted when switching to or \
+                     from dB",
+                )
+                .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                if committed(
+                    ui.add(
+                        egui::DragValue::new(spectrum_smoothing)
+                            .clamp_range(1..=101)
+                            .prefix("Smoothing: ")
+                            .suffix(" bins"),
+                    ),
+                    *spectrum_smoothing,
+                )
+                .on_hover_text("Moving average over this many spectrum bins. 1 disables it")
+                .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                if ui
+                    .checkbox(spectrum_log_freq, "Logarithmic frequency axis")
+                    .changed()
+                {
+                    // The stored view is meaningless on the other axis.
+                    spectrum_view.reset();
+                }
+                egui::ComboBox::from_label("Phase unit")
+                    .selected_text(phase_unit.name())
+                    .show_ui(ui, |ui| {
+                        for unit in PhaseUnit::ALL {
+                            ui.selectable_value(phase_unit, unit, unit.name());
+                        }
+                    });
+                egui::ComboBox::from_label("Amplitude unit")
+                    .selected_text(amplitude_unit.name())
+                    .show_ui(ui, |ui| {
+                        for unit in AmplitudeUnit::ALL {
+                            ui.selectable_value(amplitude_unit, unit, unit.name());
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(display_precision)
+                        .clamp_range(0..=12)
+                        .prefix("Decimal places: "),
+                )
+                .on_hover_text("Precision of the parameters, readouts and the exported report");
+                ui.add(
+                    egui::DragValue::new(reference_a)
+                        .clamp_range(300.0..=500.0)
+                        .speed(0.1)
+                        .prefix("Reference A4: ")
+                        .suffix(" Hz"),
+                )
+                .on_hover_text("Pitch that note names entered on the components are tuned to");
+                waveform_line.show(ui, "Waveform line");
+                spectrum_line.show(ui, "Spectrum line");
+                waveform_axes.show(ui, "Waveform axes", [-1.5, 1.5]);
+                spectrum_axes.show(ui, "Spectrum axes", spectrum_range(*spectrum_db));
+                if ui
+                    .checkbox(show_individual_waveforms, "Show individual waveforms")
+                    .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                if ui
+                    .checkbox(continuous_waveform, "Show continuous waveform")
+                    .on_hover_text(
+                        "Draw the signal between the samples, computed at a higher sample rate, \
+                         and the samples as dots on it.\nShows what aliasing does to the \
+                         sampled signal. Not shown while a filter is set",
+                    )
+                    .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                ui.add_enabled(
+                    !*continuous_waveform || filter.is_some(),
+                    egui::Checkbox::new(show_sample_points, "Show sample points"),
+                )
+                .on_hover_text("Mark each sample on the waveform line with a dot")
+                .on_disabled_hover_text("The continuous waveform always shows the samples as dots");
+                ui.horizontal(|ui| {
+                    let mut limit = plot_point_limit.is_some();
+                    let mut points = plot_point_limit.unwrap_or(DEFAULT_PLOT_POINTS);
+                    if ui
+                        .checkbox(&mut limit, "Limit plotted points to")
+                        .on_hover_text(
+                            "Draw only the lowest and highest sample of each stretch of the \
+                             waveform,\nto speed up drawing long waveforms. Exports and the \
+                             spectrum still use all samples.",
+                        )
+                        .changed()
+                        | committed(
+                            ui.add_enabled(
+                                limit,
+                                egui::DragValue::new(&mut points)
+                                    .clamp_range(2..=1_000_000)
+                                    .speed(10),
+                            ),
+                            points,
+                        )
+                        .changed()
+                    {
+                        *plot_point_limit = limit.then_some(points);
+                        plot_data_cache.invalidate();
+                    }
+                });
+                ui.checkbox(show_phase, "Show phase spectrum");
+                let mut show_correlation = correlation.is_some();
+                if ui
+                    .checkbox(&mut show_correlation, "Show correlation")
+                    .on_hover_text("Cross-correlate or convolve two of the components")
+                    .changed()
+                {
+                    *correlation = show_correlation.then(|| CorrelationSetup {
+                        operation: correlation::Operation::default(),
+                        pair: [0, usize::from(components.len() > 1)],
+                    });
+                }
+                if ui
+                    .add_enabled(
+                        *show_phase,
+                        egui::Checkbox::new(unwrap_phase, "Unwrap phase"),
+                    )
+                    .changed()
+                {
+                    plot_data_cache.invalidate();
+                }
+                if ui.checkbox(show_spectrogram, "Show spectrogram").changed() {
+                    plot_data_cache.invalidate();
+                }
+                ui.add_enabled_ui(*show_spectrogram, |ui| {
+                    let previous_size = *spectrogram_window_size;
+                    egui::ComboBox::from_label("Spectrogram window")
+                        .selected_text(format!("{spectrogram_window_size} samples"))
+                        .show_ui(ui, |ui| {
+                            for size in spectrogram::WINDOW_SIZES {
+                                ui.selectable_value(
+                                    spectrogram_window_size,
+                                    size,
+                                    format!("{size} samples"),
+                                );
+                            }
+                        });
+                    let overlap_changed = ui
+                        .add(
+                            egui::Slider::new(spectrogram_overlap, 0.0..=0.9)
+                                .text("Spectrogram overlap"),
+                        )
+                        .changed();
+                    if *spectrogram_window_size != previous_size || overlap_changed {
+                        plot_data_cache.invalidate();
+                    }
+                });
+
+                ui.separator();
+
+                ui.heading("Display");
+                egui::Grid::new("display_settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (label, text) in [
+                            ("Waveform title", &mut *waveform_title),
+                            ("Waveform X axis", &mut *waveform_x_label),
+                            ("Waveform Y axis", &mut *waveform_y_label),
+                            ("Spectrum title", &mut *spectrum_title),
+                            ("Spectrum X axis", &mut *spectrum_x_label),
+                            ("Spectrum Y axis", &mut *spectrum_y_label),
+                        ] {
+                            let label = ui.label(label);
+                            ui.text_edit_singleline(text).labelled_by(label.id);
+                            ui.end_row();
+                        }
+                    });
+                ui.checkbox(show_perf_panel, "Show performance panel");
+                ui.checkbox(show_component_table, "Show component table");
+            });
         });
 
         egui::SidePanel::right("right_panel").show(ctx, |ui| {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for c in components.iter_mut().filter(|c| c.enabled) {
-                    egui::Frame::none()
-                        .fill(ui.visuals().faint_bg_color)
-                        .outer_margin(10.0)
-                        .show(ui, |ui| {
-                            ui.vertical(|ui| {
-                                c.show(ui, *sample_rate, plot_data_cache);
-                            });
-                        });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(component_filter).hint_text("🔍 Filter components"),
+                )
+                .on_hover_text(
+                    "Only list the components whose name contains this.\n\
+                     Hidden components are still part of the waveform",
+                );
+                if !component_filter.is_empty() && ui.small_button("✖").clicked() {
+                    component_filter.clear();
                 }
             });
-        });
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            // The central panel the region left after adding TopPanel's and SidePanel's
-
-            ui.heading("Plot");
+            let filter = component_filter.to_lowercase();
+            let matches = |c: &ComponentWrapper| c.name.to_lowercase().contains(&filter);
 
-            let pd = plot_data_cache.get_or_init(|| {
-                let waveform: Vec<_> = Waveform::<f64, f64>::with_components(
-                    *sample_rate,
-                    components.iter().map(|c| c.inner.build()).collect(),
-                )
+            // Indices of the components in each group, in the order the groups first appear.
+            let mut sections: Vec<(Option<String>, Vec<usize>)> = vec![];
+            for (i, c) in components
                 .iter()
-                .take(*n_samples as usize)
-                .collect();
+                .enumerate()
+                .filter(|(_, c)| c.enabled && matches(c))
+            {
+                match sections.iter_mut().find(|(group, _)| *group == c.group) {
+                    Some((_, indices)) => indices.push(i),
+                    None => sections.push((c.group.clone(), vec![i])),
+                }
+            }
+            // Ungrouped components come first.
+            sections.sort_by_key(|(group, _)| group.is_some());
+            let any_groups = sections.iter().any(|(group, _)| group.is_some());
+            let mut action = None;
+            let mut toggled_group = None;
 
-                PlotData {
-                    waveform: {
-                        waveform
-                            .iter()
-                            .enumerate()
-                            .map(|(i, x)| [i as f64 / *sample_rate, *x])
-                            .collect()
-                    },
-                    spectrum: {
-                        let fmax = *sample_rate / FMAX_SCALE;
-                        let spectrum_resolution = *sample_rate / f64::from(*n_samples);
-                        let mut buffer: Vec<_> =
-                            waveform.into_iter().map(|s| Complex::new(s, 0.0)).collect();
-                        let fft = FFT_PLANNER
-                            .lock()
-                            .expect("Could not get lock on FFT_PLANNER")
-                            .plan_fft_forward(*n_samples as usize);
-                        fft.process(&mut buffer);
-                        buffer
+            // The arrow keys move between the listed cards, and Enter expands or collapses the
+            // focused one.
+            let mut toggle_focused = false;
+            let mut scroll_to_focused = false;
+            if !ctx.wants_keyboard_input() {
+                let order: Vec<usize> = sections
+                    .iter()
+                    .flat_map(|(_, indices)| indices.iter().copied())
+                    .collect();
+                let position = focused_card.and_then(|f| order.iter().position(|&i| i == f));
+                let mut input = ctx.input_mut();
+                let next = if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                    Some(position.map_or(0, |p| p + 1))
+                } else if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                    Some(position.map_or(order.len(), |p| p.max(1)) - 1)
+                } else {
+                    None
+                };
+                if let Some(&i) = next.and_then(|p| order.get(p)) {
+                    *focused_card = Some(i);
+                    scroll_to_focused = true;
+                }
+                toggle_focused = position.is_some()
+                    && input.consume_key(egui::Modifiers::NONE, egui::Key::Enter);
+            }
+
+            if !selected.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(format!("{} selected", selected.len()));
+                    if ui.button("Select all").clicked() {
+                        *selected = (0..components.len()).collect();
+                    }
+                    if ui.button("Select none").clicked() {
+                        selected.clear();
+                    }
+                });
+                ui.horizontal_wrapped(|ui| {
+                    let mut changed = false;
+                    if ui.button("🗑 Delete").clicked() {
+                        // Removed at the end of the frame, which keeps the indices pointing at
+                        // the right components.
+                        for &i in selected.iter() {
+                            components[i].enabled = false;
+                        }
+                        changed = true;
+                    }
+                    for (label, mute) in [("🔇 Mute", true), ("🔊 Unmute", false)] {
+                        if ui.button(label).clicked() {
+                            for &i in selected.iter() {
+                                components[i].muted = mute;
+                            }
+                            changed = true;
+                        }
+                    }
+                    if ui
+                        .button("Scale amplitudes")
+                        .on_hover_text("Multiply the amplitudes of the selected components")
+                        .clicked()
+                    {
+                        for &i in selected.iter() {
+                            let [_, amplitude, _] = components[i].inner.basic_parameters_mut();
+                            if let Some(a) = amplitude {
+                                *a *= *batch_scale;
+                            }
+                        }
+                        changed = true;
+                    }
+                    ui.add(
+                        egui::DragValue::new(batch_scale)
+                            .clamp_range(0.0..=f64::MAX)
+                            .speed(0.01)
+                            .prefix("×"),
+                    );
+                    if changed {
+                        plot_data_cache.invalidate();
+                    }
+                });
+                ui.separator();
+            }
+
+            if sections.is_empty() && components.iter().any(|c| c.enabled) {
+                ui.label("No components match the filter.");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (group, visible) in &sections {
+                    let mut show_cards = |ui: &mut egui::Ui| {
+                        for (pos, &i) in visible.iter().enumerate() {
+                            let c = &mut components[i];
+                            let is_focused = *focused_card == Some(i);
+                            let mut state =
+                                egui::collapsing_header::CollapsingState::load_with_default_open(
+                                    ui.ctx(),
+                                    ui.make_persistent_id(("card", i)),
+                                    true,
+                                );
+                            if is_focused && toggle_focused {
+                                state.toggle(ui);
+                            }
+                            let focus_before = ui.memory().focus();
+                            let card = egui::Frame::none()
+                                .fill(ui.visuals().faint_bg_color)
+                                .stroke(if is_focused {
+                                    ui.visuals().selection.stroke
+                                } else {
+                                    egui::Stroke::NONE
+                                })
+                                .outer_margin(10.0)
+                                .show(ui, |ui| {
+                                    ui.push_id(i, |ui| {
+                                        state
+                                            .show_header(ui, |ui| {
+                                                let mut is_selected = selected.contains(&i);
+                                                let response = ui
+                                                    .checkbox(&mut is_selected, "Select")
+                                                    .on_hover_text("Select for batch operations");
+                                                if is_focused && tab_into_card {
+                                                    response.request_focus();
+                                                }
+                                                if response.changed() {
+                                                    if is_selected {
+                                                        selected.insert(i);
+                                                    } else {
+                                                        selected.remove(&i);
+                                                    }
+                                                }
+                                                let mut is_isolated = *isolated == Some(i);
+                                                if ui
+                                                    .checkbox(&mut is_isolated, "Isolate spectrum")
+                                                    .on_hover_text(
+                                                        "Show only this component's spectrum. \
+                                                         The waveform is not affected.",
+                                                    )
+                                                    .changed()
+                                                {
+                                                    *isolated = is_isolated.then_some(i);
+                                                }
+                                            })
+                                            .body_unindented(|ui| {
+                                                let neighbours = (
+                                                    pos.checked_sub(1).map(|p| visible[p]),
+                                                    visible.get(pos + 1).copied(),
+                                                );
+                                                if let Some(a) = c.show(
+                                                    ui,
+                                                    *sample_rate / *decimation as f64 / *fmax_scale,
+                                                    *n_samples as f64 / *sample_rate,
+                                                    (*phase_unit, *amplitude_unit, *reference_a),
+                                                    neighbours,
+                                                    plot_data_cache,
+                                                ) {
+                                                    action = Some((i, a));
+                                                }
+                                            });
+                                    });
+                                });
+                            // Tabbing to or clicking into a card focuses it.
+                            let focus_after = ui.memory().focus();
+                            let pressed = ui.input().pointer.any_pressed();
+                            let clicked = pressed && ui.rect_contains_pointer(card.response.rect);
+                            if clicked || (focus_after.is_some() && focus_after != focus_before) {
+                                *focused_card = Some(i);
+                            } else if is_focused && scroll_to_focused {
+                                ui.scroll_to_rect(card.response.rect, None);
+                            }
+                        }
+                    };
+                    match group {
+                        None if !any_groups => show_cards(ui),
+                        None => {
+                            egui::CollapsingHeader::new("Ungrouped")
+                                .default_open(true)
+                                .show(ui, show_cards);
+                        }
+                        Some(name) => {
+                            let id = ui.make_persistent_id(("group", name));
+                            egui::collapsing_header::CollapsingState::load_with_default_open(
+                                ui.ctx(),
+                                id,
+                                true,
+                            )
+                            .show_header(ui, |ui| {
+                                let mut enabled = !disabled_groups.contains(name);
+                                if ui
+                                    .checkbox(&mut enabled, name.as_str())
+                                    .on_hover_text("Include this group in the waveform")
+                                    .changed()
+                                {
+                                    toggled_group = Some(name.clone());
+                                }
+                            })
+                            .body(show_cards);
+                        }
+                    }
+                }
+            });
+
+            if let Some(group) = toggled_group {
+                if !disabled_groups.remove(&group) {
+                    disabled_groups.insert(group);
+                }
+                plot_data_cache.invalidate();
+            }
+            if let Some((i, action)) = action {
+                match action {
+                    ComponentAction::SwapWith(j) => {
+                        components.swap(i, j);
+                        for index in [&mut *isolated, &mut *focused_card, &mut *thd_fundamental] {
+                            if *index == Some(i) {
+                                *index = Some(j);
+                            } else if *index == Some(j) {
+                                *index = Some(i);
+                            }
+                        }
+                        for index in correlation.iter_mut().flat_map(|c| &mut c.pair) {
+                            if *index == i {
+                                *index = j;
+                            } else if *index == j {
+                                *index = i;
+                            }
+                        }
+                        // The selection follows the components.
+                        if selected.contains(&i) != selected.contains(&j) {
+                            for k in [i, j] {
+                                if !selected.remove(&k) {
+                                    selected.insert(k);
+                                }
+                            }
+                        }
+                    }
+                    ComponentAction::Duplicate => {
+                        let mut copy = components[i].clone();
+                        copy.name.push_str(" (copy)");
+                        copy.enabled = true;
+                        components.push(copy);
+                    }
+                    ComponentAction::ScaleLinked(factor) => {
+                        for (j, c) in components.iter_mut().enumerate() {
+                            if j != i && c.linked {
+                                c.inner.scale_frequencies(factor);
+                            }
+                        }
+                    }
+                    ComponentAction::SnapWindow(periods) => {
+                        let period = *sample_rate / components[i].detuned().frequency();
+                        #[allow(
+                            clippy::cast_possible_truncation,
+                            clippy::cast_precision_loss,
+                            clippy::cast_sign_loss
+                        )]
+                        let snapped = (periods as f64 * period).round() as usize;
+                        *n_samples = snapped.max(1);
+                        // Padding the samples with zeros up to the FFT size would bring the
+                        // leakage back.
+                        let analyzed = (*n_samples + *decimation - 1) / *decimation;
+                        if fft_size.unwrap_or_else(|| analyzed.next_power_of_two()) != analyzed {
+                            *fft_size = Some(analyzed);
+                        }
+                    }
+                }
+                plot_data_cache.invalidate();
+            }
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // The central panel the region left after adding TopPanel's and SidePanel's
+
+            // Each plot marks where the pointer was on the others in the last frame, and the
+            // plot it is on now takes over.
+            let hover = linked_hover.take();
+
+            ui.horizontal(|ui| {
+                ui.heading(waveform_title.as_str());
+                if saturation.is_some() {
+                    ui.label("(saturated)");
+                }
+                if let Some(filter) = filter {
+                    ui.label(format!("({} filtered)", filter.kind.name().to_lowercase()));
+                }
+                if let Some(bits) = bit_depth {
+                    ui.label(format!("(quantized to {bits} bits)"));
+                }
+                if *decimation > 1 {
+                    #[allow(clippy::cast_precision_loss)]
+                    let rate = *sample_rate / *decimation as f64;
+                    ui.label(format!("(decimated by {decimation}, {rate} Hz)"));
+                }
+                if ui
+                    .button("Reset view")
+                    .on_hover_text("Fit both plots to their data again")
+                    .clicked()
+                {
+                    waveform_view.reset();
+                    spectrum_view.reset();
+                }
+                ui.separator();
+                if ui
+                    .add_enabled(
+                        plot_data_cache.is_valid(),
+                        egui::Button::new("📌 Set reference"),
+                    )
+                    .on_hover_text(
+                        "Keep the current signal as a reference, to compare changes against",
+                    )
+                    .clicked()
+                {
+                    // The snapshot takes the data over; the current signal is computed again.
+                    *reference = plot_data_cache.take();
+                }
+                if reference.is_some() && ui.button("Clear reference").clicked() {
+                    *reference = None;
+                }
+                ui.separator();
+                waveform_markers.toolbar(ui);
+            });
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let imported = imported.as_deref();
+            #[cfg(target_arch = "wasm32")]
+            let imported: Option<&[f64]> = None;
+            if let Some(samples) = imported {
+                ui.label(format!(
+                    "Showing the imported WAV file ({} samples) instead of the components.",
+                    samples.len()
+                ));
+            } else if *sketch_spectrum {
+                ui.label("Showing the waveform synthesized from the sketched spectrum.");
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let has_focus = ctx.input().raw.has_focus;
+            #[cfg(not(target_arch = "wasm32"))]
+            if *free_when_unfocused && !has_focus {
+                plot_data_cache.invalidate();
+                isolated_spectrum.invalidate();
+                correlation_plot.invalidate();
+                ui.label("The plots are dropped while the window is unfocused, to free memory.");
+                return;
+            }
+
+            if !plot_data_cache.is_valid()
+                && *n_samples > *max_samples
+                && *confirmed_samples != Some(*n_samples)
+            {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "⚠ {n_samples} samples is more than the limit of {max_samples}. \
+                         Computing the plots may freeze the app for a while."
+                    ),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Compute anyway").clicked() {
+                        *confirmed_samples = Some(*n_samples);
+                    }
+                    if ui.button(format!("Reduce to {max_samples}")).clicked() {
+                        *n_samples = *max_samples;
+                    }
+                });
+                return;
+            }
+
+            for pd in plot_data_cache
+                .get_mut()
+                .into_iter()
+                .chain(reference.as_mut())
+            {
+                pd.poll_spectrum();
+            }
+            #[allow(clippy::cast_precision_loss)]
+            let spectrum_settings = spectrum::Settings {
+                sample_rate: *sample_rate / *decimation as f64,
+                fmax_scale: *fmax_scale,
+                window: *window,
+                fft_size: *fft_size,
+                zero_pad_factor: *zero_pad_factor,
+                norm: *spectrum_norm,
+                db: *spectrum_db,
+                psd: *spectrum_psd,
+                two_sided: *two_sided_spectrum,
+                peak_threshold: *peak_threshold,
+                smoothing: *spectrum_smoothing,
+                unwrap_phase: *unwrap_phase,
+            };
+            // Everything the isolated spectrum depends on invalidates the plot data too.
+            let generation = plot_data_cache.generation();
+            let pd = plot_data_cache.get_or_init(|| {
+                let any_solo = components.iter().any(|c| c.solo);
+                let band_limit = band_limited.then_some(*sample_rate / 2.0);
+                // Samples of the summed components, keyed by their index in the component list.
+                let mut component_samples: Vec<(usize, Vec<f64>)> =
+                    if imported.is_none() && !*sketch_spectrum {
+                        components
                             .iter()
                             .enumerate()
+                            .filter(|(_, c)| {
+                                c.enabled && c.active && c.is_audible(any_solo, disabled_groups)
+                            })
                             .map(|(i, c)| {
-                                [
-                                    i as f64 * spectrum_resolution,
-                                    c.norm() / f64::from(*n_samples),
-                                ]
+                                let samples = c
+                                    .samples(*sample_rate, *n_samples, band_limit)
+                                    .iter()
+                                    .map(|s| s * *master_gain)
+                                    .collect();
+                                (i, samples)
                             })
-                            .take_while(|[f, _]| *f < fmax)
                             .collect()
+                    } else {
+                        vec![]
+                    };
+                // Adds up the samples of the components on the channels `on` accepts.
+                let sum = |component_samples: &[(usize, Vec<f64>)], on: fn(Channel) -> bool| {
+                    let mut sum = vec![0.0; *n_samples];
+                    for (i, samples) in component_samples {
+                        if on(components[*i].channel) {
+                            for (s, c) in sum.iter_mut().zip(samples) {
+                                *s += c;
+                            }
+                        }
+                    }
+                    sum
+                };
+                let mut waveform: Vec<_> = match imported {
+                    Some(samples) => samples
+                        .iter()
+                        .take(*n_samples)
+                        .map(|s| s * *master_gain)
+                        .collect(),
+                    None if *sketch_spectrum => spectral_sketch
+                        .samples(*sample_rate, *n_samples)
+                        .iter()
+                        .map(|s| s * *master_gain)
+                        .collect(),
+                    None => sum(&component_samples, Channel::left),
+                };
+                // `waveform` is the left channel of a stereo signal.
+                let stereo = component_samples
+                    .iter()
+                    .any(|(i, _)| components[*i].channel != Channel::Both);
+                let mut right = stereo.then(|| sum(&component_samples, Channel::right));
+                for channel in std::iter::once(&mut waveform).chain(&mut right) {
+                    if let Some(saturation) = saturation {
+                        saturation.apply(channel);
+                    }
+                    if let Some(filter) = filter {
+                        filter.apply(channel, *sample_rate);
+                    }
+                }
+                // Both channels are scaled alike, to keep their balance.
+                let peak = waveform
+                    .iter()
+                    .chain(right.iter().flatten())
+                    .fold(0.0_f64, |peak, s| peak.max(s.abs()));
+                let normalization = (*auto_normalize && peak > 0.0).then(|| 1.0 / peak);
+                if let Some(factor) = normalization {
+                    let components = component_samples.iter_mut().flat_map(|(_, s)| s);
+                    for s in waveform
+                        .iter_mut()
+                        .chain(right.iter_mut().flatten())
+                        .chain(components)
+                    {
+                        *s *= factor;
+                    }
+                }
+                if let Some(bits) = bit_depth {
+                    for channel in std::iter::once(&mut waveform).chain(&mut right) {
+                        crate::util::quantize(channel, *bits);
+                    }
+                }
+                // The summed components before sampling, not quantized. A filter would respond
+                // differently at the higher rate, so the samples wouldn't lie on a filtered curve.
+                let show_continuous = *continuous_waveform
+                    && imported.is_none()
+                    && !*sketch_spectrum
+                    && filter.is_none();
+                let continuous = show_continuous.then(|| {
+                    let factor = (MAX_CONTINUOUS_POINTS / (*n_samples).max(1))
+                        .clamp(1, CONTINUOUS_OVERSAMPLING);
+                    #[allow(clippy::cast_precision_loss)]
+                    let rate = *sample_rate * factor as f64;
+                    let mut sum = vec![0.0; *n_samples * factor];
+                    for (i, _) in &component_samples {
+                        let c = &components[*i];
+                        if c.channel.left() {
+                            let samples = c.samples(rate, sum.len(), band_limit);
+                            for (s, x) in sum.iter_mut().zip(samples) {
+                                *s += x * *master_gain;
+                            }
+                        }
+                    }
+                    if let Some(saturation) = saturation {
+                        saturation.apply(&mut sum);
+                    }
+                    let scale = normalization.unwrap_or(1.0);
+                    #[allow(clippy::cast_precision_loss)]
+                    sum.iter()
+                        .enumerate()
+                        .map(|(i, s)| [i as f64 / rate, s * scale])
+                        .collect()
+                });
+                // Everything from here on works at the decimated sample rate.
+                #[allow(clippy::cast_precision_loss)]
+                let sample_rate = *sample_rate / *decimation as f64;
+                if *decimation > 1 {
+                    let decimate = |samples: &mut Vec<f64>| {
+                        *samples = samples.iter().step_by(*decimation).copied().collect();
+                    };
+                    decimate(&mut waveform);
+                    if let Some(right) = &mut right {
+                        decimate(right);
+                    }
+                    for (_, samples) in &mut component_samples {
+                        decimate(samples);
+                    }
+                }
+
+                let to_points = |samples: &[f64]| {
+                    samples
+                        .iter()
+                        .enumerate()
+                        .map(|(i, x)| [i as f64 / sample_rate, *x])
+                        .collect()
+                };
+                let reduce = |points: &[[f64; 2]]| match *plot_point_limit {
+                    Some(limit) => crate::util::reduce_for_display(points, limit),
+                    None => points.to_vec(),
+                };
+                let waveform_points: Vec<_> = to_points(&waveform);
+                let component_waveforms = if *show_individual_waveforms {
+                    component_samples
+                        .iter()
+                        .map(|(i, samples)| (*i, reduce(&to_points(samples))))
+                        .collect()
+                } else {
+                    vec![]
+                };
+                let spectrogram = show_spectrogram.then(|| {
+                    let settings = spectrogram::Settings {
+                        sample_rate,
+                        fmax_scale: *fmax_scale,
+                        window: *window,
+                        window_size: *spectrogram_window_size,
+                        overlap: *spectrogram_overlap,
+                    };
+                    Spectrogram::new(ctx, &waveform, &settings)
+                });
+                let right_channel = right.as_ref().map(|samples| {
+                    let waveform = to_points(samples);
+                    RightChannel {
+                        display_waveform: reduce(&waveform),
+                        waveform,
+                        #[cfg(not(target_arch = "wasm32"))]
+                        spectrum: spectrum::Spectrum::default(),
+                        #[cfg(target_arch = "wasm32")]
+                        spectrum: spectrum::compute(samples, &spectrum_settings),
+                    }
+                });
+
+                PlotData {
+                    sample_rate,
+                    display_waveform: reduce(&waveform_points),
+                    waveform: waveform_points,
+                    component_waveforms,
+                    normalization,
+                    continuous: continuous.map(|points: Vec<_>| reduce(&points)),
+                    right: right_channel,
+                    stats: Stats::new(&waveform),
+                    spectrogram: spectrogram.flatten(),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    spectrum: spectrum::Spectrum::default(),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    spectrum_job: Some(spectrum::Job::spawn(
+                        waveform,
+                        component_samples,
+                        right,
+                        spectrum_settings,
+                        ctx.clone(),
+                    )),
+                    #[cfg(target_arch = "wasm32")]
+                    spectrum: spectrum::compute_with_components(
+                        &waveform,
+                        &component_samples,
+                        &spectrum_settings,
+                    ),
+                }
+            });
+
+            // Exports of the spectrum wait for the background job, instead of writing an empty one.
+            let export_ready = pending_export
+                .as_ref()
+                .map_or(false, |e| !e.needs_spectrum() || !pd.spectrum_pending());
+            if export_ready {
+                if let Some(export) = pending_export.take() {
+                    if let Err(e) = export.run(pd, pd.sample_rate) {
+                        *error = Some(format!("Export failed: {e}"));
+                    }
+                }
+            }
+
+            #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+            if play {
+                let channel = |points: &[[f64; 2]]| points.iter().map(|[_, y]| *y).collect();
+                let mut channels = vec![channel(&pd.waveform)];
+                if let Some(right) = &pd.right {
+                    channels.push(channel(&right.waveform));
+                }
+                match audio::Playback::start(channels, pd.sample_rate, ctx.clone()) {
+                    Ok(p) => *playback = Some(p),
+                    Err(e) => *error = Some(format!("Could not start playback: {e}")),
+                }
+            }
+
+            #[allow(clippy::cast_precision_loss)]
+            let points = egui::plot::PlotPoints::from(pd.display_waveform.clone());
+            let name = match (pd.right.is_some(), bit_depth.is_some()) {
+                (false, false) => "Sum",
+                (false, true) => "Sum (quantized)",
+                (true, false) => "Left",
+                (true, true) => "Left (quantized)",
+            };
+            let line = waveform_line.apply(egui::plot::Line::new(points).name(name));
+            let hovered = labelled_plot(ui, waveform_x_label, waveform_y_label, |ui| {
+                let mut plot = waveform_view
+                    .apply(waveform_axes.apply(egui::plot::Plot::new("wf_plot").view_aspect(4.0)));
+                if !pd.component_waveforms.is_empty() || reference.is_some() || pd.right.is_some() {
+                    plot = plot.legend(egui::plot::Legend::default());
+                }
+                let response = plot.show(ui, |plot_ui| {
+                    waveform_view.record(plot_ui);
+                    if let Some(reference) = reference {
+                        plot_ui.line(
+                            egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                reference.display_waveform.clone(),
+                            ))
+                            .color(REFERENCE_COLOR)
+                            .name("Reference"),
+                        );
+                    }
+                    for (i, points) in &pd.component_waveforms {
+                        if let Some(c) = components.get(*i) {
+                            plot_ui.line(
+                                egui::plot::Line::new(egui::plot::PlotPoints::from(points.clone()))
+                                    .color(c.color)
+                                    .name(&c.name),
+                            );
+                        }
+                    }
+                    match &pd.continuous {
+                        Some(continuous) => plot_ui.line(
+                            waveform_line
+                                .apply(egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                    continuous.clone(),
+                                )))
+                                .name("Continuous"),
+                        ),
+                        None => plot_ui.line(line),
+                    }
+                    if *show_sample_points || pd.continuous.is_some() {
+                        plot_ui.points(
+                            egui::plot::Points::new(egui::plot::PlotPoints::from(
+                                pd.display_waveform.clone(),
+                            ))
+                            .radius(2.5)
+                            .name(name),
+                        );
+                    }
+                    if let Some(right) = &pd.right {
+                        plot_ui.line(
+                            waveform_line
+                                .apply(egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                    right.display_waveform.clone(),
+                                )))
+                                .color(RIGHT_CHANNEL_COLOR)
+                                .name("Right"),
+                        );
+                    }
+                    waveform_markers.handle_clicks(plot_ui, |x| x);
+                    waveform_markers.show(plot_ui, Some);
+                    let duration = pd.waveform.last().map_or(0.0, |p| p[0]);
+                    for t in hover.map(|h| h.times(duration)).unwrap_or_default() {
+                        plot_ui.vline(
+                            egui::plot::VLine::new(t)
+                                .color(LINKED_HOVER_COLOR)
+                                .style(egui::plot::LineStyle::dotted_dense()),
+                        );
+                    }
+                    plot_ui.pointer_coordinate()
+                });
+                waveform_view.handle_response(&response.response);
+                response.inner
+            });
+            if let Some(p) = hovered {
+                *linked_hover = Some(LinkedHover::Time(p.x));
+            }
+            ui.horizontal(|ui| {
+                let Stats {
+                    rms,
+                    peak,
+                    crest_factor,
+                } = pd.stats;
+                // The statistics are of the left channel only.
+                let channel = if pd.right.is_some() { " (left)" } else { "" };
+                readout(
+                    ui,
+                    &format!("RMS{channel}"),
+                    Some(format!("{rms:.precision$}")),
+                );
+                ui.separator();
+                readout(
+                    ui,
+                    &format!("Peak{channel}"),
+                    Some(format!("{peak:.precision$}")),
+                );
+                ui.separator();
+                readout(
+                    ui,
+                    &format!("Crest factor{channel}"),
+                    crest_factor.map(|c| format!("{c:.precision$}")),
+                );
+                ui.separator();
+                if waveform_markers.active {
+                    let span = waveform_markers.span();
+                    readout(ui, "Δt", span.map(|dt| format!("{dt:.precision$} s")));
+                    readout(
+                        ui,
+                        "1/Δt",
+                        span.filter(|dt| *dt > 0.0)
+                            .map(|dt| format!("{:.precision$} Hz", 1.0 / dt)),
+                    );
+                    ui.separator();
+                }
+                ui.label(
+                    hovered
+                        .map(|p| {
+                            format!("t = {:.precision$} s, amplitude = {:.precision$}", p.x, p.y)
+                        })
+                        .unwrap_or_default(),
+                );
+            });
+
+            if let Some(reference) = reference {
+                ui.heading("Difference (current − reference)");
+                #[allow(clippy::float_cmp)]
+                if reference.sample_rate == pd.sample_rate {
+                    let difference: Vec<_> = pd
+                        .waveform
+                        .iter()
+                        .zip(&reference.waveform)
+                        .map(|([t, current], [_, reference])| [*t, current - reference])
+                        .collect();
+                    let difference = match *plot_point_limit {
+                        Some(limit) => crate::util::reduce_for_display(&difference, limit),
+                        None => difference,
+                    };
+                    labelled_plot(ui, waveform_x_label, waveform_y_label, |ui| {
+                        egui::plot::Plot::new("difference_plot")
+                            .view_aspect(4.0)
+                            .show(ui, |plot_ui| {
+                                plot_ui.line(egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                    difference,
+                                )));
+                            });
+                    });
+                } else {
+                    ui.label(
+                        "The reference has a different sample rate, so it can't be subtracted \
+                         sample by sample.",
+                    );
+                }
+            }
+
+            if let Some(s) = isolated_spectrum.get_mut() {
+                if Some(s.index) != *isolated || s.generation != generation {
+                    isolated_spectrum.invalidate();
+                }
+            }
+            // The isolated component is left as it is built: not normalized or quantized.
+            let isolated_component = isolated.and_then(|i| Some((i, components.get(i)?)));
+            let shown_spectrum = match isolated_component {
+                Some((index, c)) => {
+                    &isolated_spectrum
+                        .get_or_init(|| {
+                            let band_limit = band_limited.then_some(*sample_rate / 2.0);
+                            let samples: Vec<_> = c
+                                .samples(*sample_rate, *n_samples, band_limit)
+                                .iter()
+                                .step_by(*decimation)
+                                .map(|s| s * *master_gain)
+                                .collect();
+                            IsolatedSpectrum {
+                                index,
+                                generation,
+                                spectrum: spectrum::compute(&samples, &spectrum_settings),
+                            }
+                        })
+                        .spectrum
+                }
+                None => &pd.spectrum,
+            };
+
+            // Sketched lines are amplitudes, so they can only be placed on a spectrum that shows
+            // them as such.
+            let can_sketch =
+                !*spectrum_psd && !*two_sided_spectrum && *spectrum_norm == SpectrumNorm::Amplitude;
+            let mut sketch_changed = false;
+            if *max_hold && !pd.spectrum_pending() {
+                held_spectrum.update(&pd.spectrum, &spectrum_settings);
+            }
+            ui.horizontal(|ui| {
+                ui.heading(spectrum_title.as_str());
+                if let Some((_, c)) = isolated_component {
+                    ui.label(format!("(isolated: {})", c.name));
+                    if ui.small_button("Show sum").clicked() {
+                        *isolated = None;
+                    }
+                } else if let Some(factor) = pd.normalization {
+                    ui.label(format!("(normalized, ×{factor:.precision$})"));
+                }
+                sketch_changed |= ui
+                    .add_enabled(
+                        can_sketch,
+                        egui::Checkbox::new(sketch_spectrum, "Sketch spectrum"),
+                    )
+                    .on_hover_text(
+                        "Click the spectrum to add spectral lines, right-click to remove the \
+                         nearest one. The waveform is synthesized from them by an inverse FFT.",
+                    )
+                    .on_disabled_hover_text(
+                        "Sketching is only available for the one-sided magnitude spectrum with \
+                         amplitude normalization",
+                    )
+                    .changed();
+                if *sketch_spectrum
+                    && ui
+                        .add_enabled(
+                            !spectral_sketch.points().is_empty(),
+                            egui::Button::new("Clear sketch"),
+                        )
+                        .clicked()
+                {
+                    spectral_sketch.clear();
+                    sketch_changed = true;
+                }
+                ui.separator();
+                if ui
+                    .checkbox(max_hold, "Max hold")
+                    .on_hover_text(
+                        "Overlay the largest value each bin of the summed spectrum has had, \
+                         to catch intermittent signals",
+                    )
+                    .changed()
+                    && !*max_hold
+                {
+                    held_spectrum.reset();
+                }
+                if *max_hold && ui.button("Reset hold").clicked() {
+                    held_spectrum.reset();
+                }
+                ui.separator();
+                spectrum_markers.toolbar(ui);
+            });
+            let sketching = *sketch_spectrum && can_sketch;
+
+            let axis = FrequencyAxis {
+                log: *spectrum_log_freq,
+            };
+            let points = egui::plot::PlotPoints::from(axis.points(&shown_spectrum.points));
+            let mut line = spectrum_line.apply(egui::plot::Line::new(points));
+            // An isolated component's spectrum is the same on both channels.
+            let right = pd.right.as_ref().filter(|_| isolated_component.is_none());
+            if right.is_some() {
+                line = line.name("Left");
+            }
+            let mut plot = spectrum_view.apply(
+                spectrum_axes.apply(
+                    egui::plot::Plot::new("spectrum_plot")
+                        .view_aspect(4.0)
+                        .legend(egui::plot::Legend::default()),
+                ),
+            );
+            if axis.log {
+                plot = plot.x_axis_formatter(FrequencyAxis::log_label);
+            }
+            let unit = match (*spectrum_psd, *spectrum_db) {
+                (false, false) => "",
+                (false, true) => " dB",
+                (true, false) => " /Hz",
+                (true, true) => " dB/Hz",
+            };
+            if !unit.is_empty() {
+                plot = plot.y_axis_formatter(move |y, _| {
+                    format!("{}{unit}", egui::emath::round_to_decimals(y, 5))
+                });
+            }
+            let y_label = normalized_label(spectrum_y_label, *spectrum_norm, *spectrum_psd);
+            let hovered = labelled_plot(ui, spectrum_x_label, &y_label, |ui| {
+                let response = plot.show(ui, |plot_ui| {
+                    spectrum_view.record(plot_ui);
+                    if pd.spectrum_pending() && isolated_component.is_none() {
+                        let bounds = plot_ui.plot_bounds();
+                        plot_ui.text(egui::plot::Text::new(
+                            egui::plot::PlotPoint::new(
+                                (bounds.min()[0] + bounds.max()[0]) / 2.0,
+                                (bounds.min()[1] + bounds.max()[1]) / 2.0,
+                            ),
+                            "Computing…",
+                        ));
+                    }
+                    if let Some(reference) = reference {
+                        plot_ui.line(
+                            egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                axis.points(&reference.spectrum.points),
+                            ))
+                            .color(REFERENCE_COLOR)
+                            .name("Reference"),
+                        );
+                    }
+                    // The hold follows the sum, so it's only comparable to that.
+                    if *max_hold && isolated_component.is_none() {
+                        plot_ui.line(
+                            egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                axis.points(held_spectrum.points()),
+                            ))
+                            .color(MAX_HOLD_COLOR)
+                            .name(if pd.right.is_some() {
+                                "Max hold (left)"
+                            } else {
+                                "Max hold"
+                            }),
+                        );
+                    }
+                    plot_ui.line(line);
+                    if let Some(right) = right {
+                        plot_ui.line(
+                            spectrum_line
+                                .apply(egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                    axis.points(&right.spectrum.points),
+                                )))
+                                .color(RIGHT_CHANNEL_COLOR)
+                                .name("Right"),
+                        );
+                    }
+                    plot_ui.points(
+                        egui::plot::Points::new(egui::plot::PlotPoints::from(
+                            axis.points(&shown_spectrum.peaks),
+                        ))
+                        .radius(3.0)
+                        .name("Peaks"),
+                    );
+                    for [f, m] in &shown_spectrum.peaks {
+                        if let Some(x) = axis.x(*f) {
+                            plot_ui.text(
+                                egui::plot::Text::new(
+                                    egui::plot::PlotPoint::new(x, *m),
+                                    format!("{f:.precision$} Hz"),
+                                )
+                                .anchor(egui::Align2::CENTER_BOTTOM),
+                            );
+                        }
+                    }
+                    // Sketching takes the clicks while it's on.
+                    if !sketching {
+                        spectrum_markers.handle_clicks(plot_ui, |x| axis.frequency(x));
+                    }
+                    spectrum_markers.show(plot_ui, |f| axis.x(f));
+                    if sketching {
+                        let to_display = |m: f64| if *spectrum_db { 20.0 * m.log10() } else { m };
+                        let lines: Vec<_> = spectral_sketch
+                            .points()
+                            .iter()
+                            .filter_map(|[f, m]| Some([axis.x(*f)?, to_display(*m)]))
+                            .collect();
+                        plot_ui.points(
+                            egui::plot::Points::new(egui::plot::PlotPoints::from(lines))
+                                .radius(4.0)
+                                .shape(egui::plot::MarkerShape::Diamond)
+                                .name("Sketch"),
+                        );
+                        if let Some(p) = plot_ui.pointer_coordinate() {
+                            let frequency = axis.frequency(p.x);
+                            if plot_ui.plot_clicked() {
+                                let magnitude = if *spectrum_db {
+                                    10_f64.powf(p.y / 20.0)
+                                } else {
+                                    p.y
+                                };
+                                if frequency >= 0.0 && magnitude > 0.0 {
+                                    spectral_sketch.add(frequency, magnitude);
+                                    sketch_changed = true;
+                                }
+                            } else if plot_ui.plot_secondary_clicked() {
+                                spectral_sketch.remove_nearest(frequency);
+                                sketch_changed = true;
+                            }
+                        }
+                    }
+                    for c in components.iter() {
+                        let (low, high) = c.detuned().frequency_range();
+                        let markers = if low == high {
+                            vec![low]
+                        } else {
+                            vec![low, high]
+                        };
+                        for x in markers.into_iter().filter_map(|f| axis.x(f)) {
+                            plot_ui.vline(egui::plot::VLine::new(x).name(c.name.clone()));
+                        }
+                    }
+                    let top = plot_ui.plot_bounds().max()[1];
+                    let nyquist = pd.sample_rate / 2.0;
+                    let fmax = pd.sample_rate / *fmax_scale;
+                    for (name, f, color) in [
+                        ("Nyquist", nyquist, egui::Color32::RED),
+                        ("fmax", fmax, egui::Color32::GRAY),
+                    ] {
+                        // When the spectrum goes up to Nyquist, one line is enough.
+                        if name == "fmax" && (fmax - nyquist).abs() < f64::EPSILON * nyquist {
+                            continue;
+                        }
+                        if let Some(x) = axis.x(f) {
+                            plot_ui.vline(
+                                egui::plot::VLine::new(x)
+                                    .color(color)
+                                    .style(egui::plot::LineStyle::dashed_loose())
+                                    .name(name),
+                            );
+                            plot_ui.text(
+                                egui::plot::Text::new(egui::plot::PlotPoint::new(x, top), name)
+                                    .color(color)
+                                    .anchor(egui::Align2::LEFT_TOP),
+                            );
+                        }
+                    }
+                    if let Some(x) = hover
+                        .and_then(LinkedHover::frequency)
+                        .and_then(|f| axis.x(f))
+                    {
+                        plot_ui.vline(
+                            egui::plot::VLine::new(x)
+                                .color(LINKED_HOVER_COLOR)
+                                .style(egui::plot::LineStyle::dotted_dense()),
+                        );
+                    }
+                    plot_ui.pointer_coordinate()
+                });
+                spectrum_view.handle_response(&response.response);
+                response.inner
+            });
+            if let Some(p) = hovered {
+                *linked_hover = Some(LinkedHover::Frequency(axis.frequency(p.x)));
+            }
+            let format_value = |m: f64| {
+                if *spectrum_db {
+                    format!("{m:.precision$}{unit}")
+                } else if *spectrum_psd {
+                    // Densities of quiet signals get tiny.
+                    format!("{m:.precision$e}{unit}")
+                } else {
+                    format!("{m:.precision$}")
+                }
+            };
+            let bin = hovered.and_then(|p| {
+                crate::util::nearest_index(&shown_spectrum.points, axis.frequency(p.x))
+            });
+            ui.horizontal(|ui| {
+                if spectrum_markers.active {
+                    let span = spectrum_markers.span();
+                    readout(ui, "Δf", span.map(|df| format!("{df:.precision$} Hz")));
+                    ui.separator();
+                }
+                ui.label(
+                    bin.map(|i| {
+                        let [f, m] = shown_spectrum.points[i];
+                        let quantity = if *spectrum_psd {
+                            "power density"
+                        } else {
+                            "magnitude"
+                        };
+                        format!("f = {f:.precision$} Hz, {quantity} = {}", format_value(m))
+                    })
+                    .unwrap_or_default(),
+                );
+            });
+            if let Some(i) = bin.filter(|_| !shown_spectrum.contributions.is_empty()) {
+                let mut ranked: Vec<_> = shown_spectrum
+                    .contributions
+                    .iter()
+                    .filter_map(|(c, values)| Some((components.get(*c)?, *values.get(i)?)))
+                    .collect();
+                ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+                egui::show_tooltip_at_pointer(
+                    ui.ctx(),
+                    egui::Id::new("spectrum_contributions"),
+                    |ui| {
+                        ui.strong(format!(
+                            "Contributions at {:.precision$} Hz",
+                            shown_spectrum.points[i][0]
+                        ));
+                        for (c, value) in ranked.iter().take(MAX_CONTRIBUTIONS) {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(c.color, "⏺");
+                                ui.label(format!("{}: {}", c.name, format_value(*value)));
+                            });
+                        }
+                        if ranked.len() > MAX_CONTRIBUTIONS {
+                            ui.label(format!("… and {} more", ranked.len() - MAX_CONTRIBUTIONS));
+                        }
                     },
+                );
+            }
+
+            let periodic: Vec<_> = components
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.inner.is_periodic())
+                .collect();
+            let fundamental = thd_fundamental
+                .and_then(|i| periodic.iter().find(|(j, _)| *j == i))
+                .or_else(|| periodic.first());
+            let thd = |points: &[[f64; 2]]| {
+                let (_, fundamental) = fundamental?;
+                spectrum_thd(
+                    points,
+                    fundamental.detuned().frequency(),
+                    *spectrum_db,
+                    *spectrum_psd,
+                )
+            };
+            if let Some((_, fundamental)) = fundamental {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("THD fundamental")
+                        .selected_text(&fundamental.name)
+                        .show_ui(ui, |ui| {
+                            for (i, c) in &periodic {
+                                ui.selectable_value(thd_fundamental, Some(*i), &c.name);
+                            }
+                        });
+                    let thd = thd(&shown_spectrum.points);
+                    let label = if pd.right.is_some() && isolated_component.is_none() {
+                        "THD (left)"
+                    } else {
+                        "THD"
+                    };
+                    readout(
+                        ui,
+                        label,
+                        thd.map(|thd| format!("{:.precision$} %", thd * 100.0)),
+                    );
+                });
+            }
+
+            // The report waits for the spectrum as well, for the THD.
+            if *pending_report && !pd.spectrum_pending() {
+                *pending_report = false;
+                // The report covers the summed signal, even when a component is isolated.
+                let report = report(
+                    *sample_rate,
+                    *n_samples,
+                    components,
+                    pd,
+                    fundamental
+                        .map(|(_, f)| f.name.as_str())
+                        .zip(thd(&pd.spectrum.points)),
+                    precision,
+                );
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Err(e) = export::save_text("report.md", "text/markdown", &report) {
+                    *error = Some(format!("Export failed: {e}"));
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    ui.output().copied_text = report;
+                }
+            }
+
+            if *show_phase {
+                ui.heading("Phase");
+
+                let points = egui::plot::PlotPoints::from(axis.points(&shown_spectrum.phase));
+                let line = egui::plot::Line::new(points);
+                let mut plot = egui::plot::Plot::new("phase_plot")
+                    .view_aspect(4.0)
+                    .y_axis_formatter(|y, _| {
+                        format!("{} rad", egui::emath::round_to_decimals(y, 5))
+                    });
+                if axis.log {
+                    plot = plot.x_axis_formatter(FrequencyAxis::log_label);
+                }
+                labelled_plot(ui, spectrum_x_label, "", |ui| {
+                    plot.show(ui, |plot_ui| plot_ui.line(line));
+                });
+            }
+
+            if let Some(filter) = filter {
+                ui.heading("Filter response");
+
+                let fmax = pd.sample_rate / *fmax_scale;
+                let n = FILTER_RESPONSE_POINTS;
+                let frequencies = (0..=n).map(|i| {
+                    let fraction = f64::from(i) / f64::from(n);
+                    if axis.log {
+                        // Three decades up to the highest frequency shown.
+                        fmax * 1e-3_f64.powf(1.0 - fraction)
+                    } else {
+                        fmax * fraction
+                    }
+                });
+                let response: Vec<_> = frequencies
+                    .map(|f| [f, 20.0 * filter.magnitude(f, *sample_rate).log10()])
+                    .filter(|[_, db]| db.is_finite())
+                    .collect();
+                let mut plot = egui::plot::Plot::new("filter_response_plot")
+                    .view_aspect(4.0)
+                    .y_axis_formatter(|y, _| {
+                        format!("{} dB", egui::emath::round_to_decimals(y, 5))
+                    });
+                if axis.log {
+                    plot = plot.x_axis_formatter(FrequencyAxis::log_label);
+                }
+                labelled_plot(ui, spectrum_x_label, "Gain", |ui| {
+                    plot.show(ui, |plot_ui| {
+                        plot_ui.line(egui::plot::Line::new(egui::plot::PlotPoints::from(
+                            axis.points(&response),
+                        )));
+                        if let Some(x) = axis.x(filter.cutoff) {
+                            plot_ui.vline(
+                                egui::plot::VLine::new(x)
+                                    .style(egui::plot::LineStyle::dashed_dense()),
+                            );
+                        }
+                    });
+                });
+            }
+
+            if let Some(setup) = correlation {
+                ui.horizontal(|ui| {
+                    ui.heading("Correlation");
+                    egui::ComboBox::from_id_source("correlation_operation")
+                        .selected_text(setup.operation.name())
+                        .show_ui(ui, |ui| {
+                            for operation in correlation::Operation::ALL {
+                                ui.selectable_value(
+                                    &mut setup.operation,
+                                    operation,
+                                    operation.name(),
+                                );
+                            }
+                        });
+                    for (side, index) in setup.pair.iter_mut().enumerate() {
+                        egui::ComboBox::from_id_source(("correlation_component", side))
+                            .selected_text(components.get(*index).map_or("–", |c| &c.name))
+                            .show_ui(ui, |ui| {
+                                for (i, c) in components.iter().enumerate() {
+                                    ui.selectable_value(index, i, &c.name);
+                                }
+                            });
+                    }
+                });
+
+                if let Some(plot) = correlation_plot.get_mut() {
+                    if plot.setup != *setup || plot.generation != generation {
+                        correlation_plot.invalidate();
+                    }
+                }
+                let [a, b] = setup.pair.map(|i| components.get(i));
+                if let (Some(a), Some(b)) = (a, b) {
+                    let plot = correlation_plot.get_or_init(|| {
+                        // Like the isolated spectrum, the components are taken as they are built.
+                        let band_limit = band_limited.then_some(*sample_rate / 2.0);
+                        let samples = |c: &ComponentWrapper| -> Vec<_> {
+                            c.samples(*sample_rate, *n_samples, band_limit)
+                                .iter()
+                                .step_by(*decimation)
+                                .map(|s| s * *master_gain)
+                                .collect()
+                        };
+                        let points: Vec<_> =
+                            correlation::compute(&samples(a), &samples(b), setup.operation)
+                                .into_iter()
+                                .map(|[k, value]| [k / pd.sample_rate, value])
+                                .collect();
+                        // About twice as long as the waveform, so reduced just like it.
+                        let points = match *plot_point_limit {
+                            Some(limit) => crate::util::reduce_for_display(&points, limit),
+                            None => points,
+                        };
+                        CorrelationPlot {
+                            setup: *setup,
+                            generation,
+                            points,
+                        }
+                    });
+                    let x_label = match setup.operation {
+                        correlation::Operation::Correlation => "Lag [s]",
+                        correlation::Operation::Convolution => waveform_x_label.as_str(),
+                    };
+                    labelled_plot(ui, x_label, "", |ui| {
+                        egui::plot::Plot::new("correlation_plot")
+                            .view_aspect(4.0)
+                            .show(ui, |plot_ui| {
+                                plot_ui.line(egui::plot::Line::new(egui::plot::PlotPoints::from(
+                                    plot.points.clone(),
+                                )));
+                            });
+                    });
+                } else {
+                    ui.label("Pick two components.");
+                }
+            }
+
+            if *show_spectrogram {
+                ui.heading(if pd.right.is_some() {
+                    "Spectrogram (left)"
+                } else {
+                    "Spectrogram"
+                });
+
+                match &pd.spectrogram {
+                    Some(spectrogram) => {
+                        #[allow(clippy::cast_possible_truncation)]
+                        let image = egui::plot::PlotImage::new(
+                            &spectrogram.texture,
+                            egui::plot::PlotPoint::new(
+                                spectrogram.duration / 2.0,
+                                spectrogram.max_frequency / 2.0,
+                            ),
+                            egui::vec2(
+                                spectrogram.duration as f32,
+                                spectrogram.max_frequency as f32,
+                            ),
+                        );
+                        let hovered = labelled_plot(ui, waveform_x_label, spectrum_x_label, |ui| {
+                            egui::plot::Plot::new("spectrogram_plot")
+                                .view_aspect(4.0)
+                                .show(ui, |plot_ui| {
+                                    plot_ui.image(image);
+                                    match hover {
+                                        Some(LinkedHover::Time(t)) => {
+                                            plot_ui.vline(
+                                                egui::plot::VLine::new(t)
+                                                    .color(LINKED_HOVER_COLOR)
+                                                    .style(egui::plot::LineStyle::dotted_dense()),
+                                            );
+                                        }
+                                        Some(LinkedHover::Frequency(f)) => {
+                                            plot_ui.hline(
+                                                egui::plot::HLine::new(f)
+                                                    .color(LINKED_HOVER_COLOR)
+                                                    .style(egui::plot::LineStyle::dotted_dense()),
+                                            );
+                                        }
+                                        _ => {}
+                                    }
+                                    plot_ui.pointer_coordinate()
+                                })
+                                .inner
+                        });
+                        if let Some(p) = hovered {
+                            *linked_hover = Some(LinkedHover::TimeFrequency(p.x, p.y));
+                        }
+                    }
+                    None => {
+                        ui.label("Not enough samples for a single spectrogram window.");
+                    }
+                }
+            }
+
+            if sketch_changed {
+                plot_data_cache.invalidate();
+            }
+        });
+
+        if let Some(message) = error.as_deref() {
+            let mut dismissed = false;
+            egui::Window::new("Error")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(message);
+                    dismissed = ui.button("OK").clicked();
+                });
+            if dismissed {
+                *error = None;
+            }
+        }
+
+        egui::Window::new("Component table")
+            .open(show_component_table)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                component_table(ui, components, *phase_unit, plot_data_cache);
+            });
+
+        if let Some(series) = harmonic_series {
+            let mut close = false;
+            egui::Window::new("Harmonic series")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    series.show(ui);
+                    ui.horizontal(|ui| {
+                        if ui.button("Add").clicked() {
+                            components.extend(series.components());
+                            plot_data_cache.invalidate();
+                            close = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                *harmonic_series = None;
+            }
+        }
+
+        if *confirm_clear {
+            egui::Window::new("Clear all components?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "This will remove all {} components.",
+                        components.len()
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Clear").clicked() {
+                            components.clear();
+                            plot_data_cache.invalidate();
+                            *confirm_clear = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            *confirm_clear = false;
+                        }
+                    });
+                });
+        }
+
+        if *confirm_reset {
+            egui::Window::new("Reset app?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("This will remove all components and restore the default settings.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Reset").clicked() {
+                            reset = true;
+                            *confirm_reset = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            *confirm_reset = false;
+                        }
+                    });
+                });
+        }
+
+        // Number keys add the first kinds in the palette.
+        if !ctx.wants_keyboard_input() {
+            let input = ctx.input();
+            for (key, kind) in SHORTCUTS.into_iter().zip(ComponentKind::ALL) {
+                if input.key_pressed(key) {
+                    components.push(ComponentWrapper::new(kind.name(), kind.default_component()));
+                    plot_data_cache.invalidate();
                 }
+            }
+        }
+
+        while let Some(i) = components.iter().position(|c| !c.enabled) {
+            components.remove(i);
+            // Indices of the removed component are dropped, later ones move down.
+            let shift = |j: usize| (j != i).then_some(if j > i { j - 1 } else { j });
+            *selected = selected.iter().filter_map(|&j| shift(j)).collect();
+            *isolated = isolated.and_then(shift);
+            *focused_card = focused_card.and_then(shift);
+            *thd_fundamental = thd_fundamental.and_then(shift);
+            // A correlation with a removed component has nothing left to show.
+            *correlation = correlation.and_then(|c| {
+                let [a, b] = c.pair.map(shift);
+                Some(CorrelationSetup {
+                    pair: [a?, b?],
+                    ..c
+                })
             });
+            plot_data_cache.invalidate();
+        }
+
+        undo.end_frame(
+            components,
+            ctx.is_using_pointer() || ctx.wants_keyboard_input(),
+        );
+        // Text fields handle these shortcuts on their own.
+        if !ctx.wants_keyboard_input() {
+            let mut input = ctx.input_mut();
+            undo_requested |= input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+            redo_requested |= input.consume_key(egui::Modifiers::COMMAND, egui::Key::Y);
+        }
+        if (undo_requested && undo.undo(components)) || (redo_requested && undo.redo(components)) {
+            plot_data_cache.invalidate();
+            selected.clear();
+            *isolated = None;
+            *focused_card = None;
+            *thd_fundamental = None;
+            *correlation = None;
+        }
+        // Whatever else removed components, don't keep selecting past the end of the list.
+        selected.retain(|&i| i < components.len());
+        *isolated = isolated.filter(|&i| i < components.len());
+        *focused_card = focused_card.filter(|&i| i < components.len());
+        *thd_fundamental = thd_fundamental.filter(|&i| i < components.len());
+
+        if reset {
+            *self = Self::default();
+            // Overwrite the stored state right away, so a crash doesn't bring the old one back.
+            if let Some(storage) = frame.storage_mut() {
+                eframe::set_value(storage, eframe::APP_KEY, self);
+            }
+        }
+    }
+}
+
+/// Shows a numeric readout as `label: value`, with a button that copies the value to the
+/// clipboard. A missing value is shown as a dash.
+fn readout(ui: &mut egui::Ui, label: &str, value: Option<String>) {
+    ui.label(format!("{label}: {}", value.as_deref().unwrap_or("–")));
+    if ui
+        .add_enabled(value.is_some(), egui::Button::new("📋").small())
+        .on_hover_text(format!("Copy {label}"))
+        .clicked()
+    {
+        ui.output().copied_text = value.unwrap_or_default();
+    }
+}
+
+/// Makes a drag value that is being typed into only report a change once the typed value is
+/// committed.
+///
+/// egui updates the value on every keystroke, so typing "1500" would go through 1, 15 and 150
+/// first, rebuilding the plots for each. Instead, the change is reported when editing ends, with
+/// Enter or by clicking elsewhere, and only if `value` differs from what it was when editing
+/// started. Steps taken with the arrow keys still count right away.
+fn committed(mut response: egui::Response, value: impl egui::emath::Numeric) -> egui::Response {
+    let value = value.to_f64();
+    let id = response.id.with("committed");
+    // The context is locked by each of these calls, so they mustn't overlap.
+    if response.has_focus() {
+        let stepped = {
+            let input = response.ctx.input();
+            input.key_down(egui::Key::ArrowUp) || input.key_down(egui::Key::ArrowDown)
+        };
+        if response.gained_focus() || (stepped && response.changed()) {
+            response.ctx.data().insert_temp(id, value);
+        } else {
+            response.changed = false;
+        }
+    } else if response.lost_focus() {
+        let start = response.ctx.data().get_temp::<f64>(id);
+        if let Some(start) = start {
+            response.ctx.data().remove::<f64>(id);
+            #[allow(clippy::float_cmp)]
+            let changed = start != value;
+            response.changed = changed;
+        }
+    }
+    response
+}
+
+/// Total harmonic distortion of the signal with the spectrum `points`, taking the component at
+/// `fundamental` Hz as the fundamental. `db` and `psd` tell how the spectrum is scaled.
+fn spectrum_thd(points: &[[f64; 2]], fundamental: f64, db: bool, psd: bool) -> Option<f64> {
+    // THD only needs amplitude ratios, so any scaling of the spectrum cancels out.
+    let amplitudes: Vec<_> = points
+        .iter()
+        .map(|[f, m]| {
+            let amplitude = if db {
+                10_f64.powf(m / 20.0)
+            } else if psd {
+                m.sqrt()
+            } else {
+                *m
+            };
+            [*f, amplitude]
+        })
+        .collect();
+    crate::util::thd(&amplitudes, fundamental)
+}
+
+/// Rust code building a `wavegen` waveform of `components` at `sample_rate`, with square and
+/// sawtooth waves `band_limited` or not.
+///
+/// Components that can't be written as code, and settings that are left out, are noted in
+/// comments.
+fn signal_code<'a>(
+    sample_rate: f64,
+    band_limited: bool,
+    components: impl Iterator<Item = &'a ComponentWrapper>,
+) -> String {
+    use std::fmt::Write as _;
+
+    let mut code =
+        format!("Waveform::<f64, f64>::with_components(\n    {sample_rate:?},\n    vec![\n");
+    for c in components {
+        let name = c.name.replace('\n', " ");
+        let omitted = c.code_omissions(band_limited);
+        // Writing to a `String` can't fail.
+        if !omitted.is_empty() {
+            writeln!(code, "        // not included: {}", omitted.join(", ")).ok();
+        }
+        match c.detuned().to_code() {
+            Some(component) => writeln!(
+                code,
+                "        // {name}\n        {},",
+                component.replace('\n', "\n        ")
+            ),
+            None => writeln!(code, "        // {name}: no wavegen equivalent"),
+        }
+        .ok();
+    }
+    code.push_str("    ],\n)");
+    code
+}
+
+/// A Markdown summary of the signal setup, its `components` and the statistics of the signal
+/// they add up to, for sharing. `thd` is the name of the fundamental and the THD, if known.
+///
+/// Measured values are written with `precision` decimal places, the parameters as they are.
+fn report(
+    sample_rate: f64,
+    n_samples: usize,
+    components: &[ComponentWrapper],
+    plot_data: &PlotData,
+    thd: Option<(&str, f64)>,
+    precision: usize,
+) -> String {
+    use std::fmt::Write as _;
+
+    let mut report = String::from("# Signal report\n\n");
+    #[allow(clippy::cast_precision_loss)]
+    let duration = n_samples as f64 / sample_rate;
+    // Writing to a `String` can't fail.
+    writeln!(report, "- Sample rate: {sample_rate} Hz").ok();
+    writeln!(report, "- Samples: {n_samples} ({duration:.precision$} s)").ok();
+    #[allow(clippy::float_cmp)]
+    if plot_data.sample_rate != sample_rate {
+        writeln!(report, "- Analyzed at: {} Hz", plot_data.sample_rate).ok();
+    }
+
+    report.push_str("\n## Components\n\n");
+    if components.is_empty() {
+        report.push_str("None.\n");
+    } else {
+        report.push_str("| Name | Type | Parameters | State |\n|---|---|---|---|\n");
+        for c in components {
+            let mut parameters = c.inner.describe();
+            if c.cents != 0.0 {
+                write!(parameters, ", detune = {} ¢", c.cents).ok();
+            }
+            if c.delay != 0.0 {
+                write!(parameters, ", delay = {} s", c.delay).ok();
+            }
+            if let Some(e) = c.envelope {
+                write!(
+                    parameters,
+                    ", ADSR = {} s / {} s / {} / {} s",
+                    e.attack, e.decay, e.sustain, e.release
+                )
+                .ok();
+            }
+            let state = match (c.active, c.muted, c.solo) {
+                (false, _, _) => "inactive",
+                (true, true, _) => "muted",
+                (true, false, true) => "solo",
+                (true, false, false) => "on",
+            };
+            writeln!(
+                report,
+                "| {} | {} | {parameters} | {state} |",
+                c.name.replace('|', "\\|"),
+                c.inner.name()
+            )
+            .ok();
+        }
+    }
+
+    let Stats {
+        rms,
+        peak,
+        crest_factor,
+    } = plot_data.stats;
+    if plot_data.right.is_some() {
+        report.push_str("\n## Statistics (left channel)\n\n");
+    } else {
+        report.push_str("\n## Statistics\n\n");
+    }
+    writeln!(report, "- RMS: {rms:.precision$}").ok();
+    writeln!(report, "- Peak: {peak:.precision$}").ok();
+    match crest_factor {
+        Some(c) => writeln!(report, "- Crest factor: {c:.precision$}"),
+        None => writeln!(report, "- Crest factor: –"),
+    }
+    .ok();
+    match thd {
+        Some((fundamental, thd)) => writeln!(
+            report,
+            "- THD: {:.precision$} % (fundamental: {fundamental})",
+            thd * 100.0
+        ),
+        None => writeln!(report, "- THD: –"),
+    }
+    .ok();
+
+    report
+}
+
+/// Key of [`Main::display_precision`] in the context's data.
+const PRECISION_KEY: &str = "display_precision";
+
+/// Decimal places that values are shown with, as set in the settings.
+fn display_precision(ctx: &egui::Context) -> usize {
+    ctx.data()
+        .get_temp(egui::Id::new(PRECISION_KEY))
+        .unwrap_or(DEFAULT_DISPLAY_PRECISION)
+}
+
+/// Shows `drag_value` with [`display_precision`] decimal places.
+///
+/// Only the text is affected: `DragValue::fixed_decimals` would also round every drag and step
+/// to that precision, so small steps would get lost.
+fn with_display_precision<'a>(
+    drag_value: egui::DragValue<'a>,
+    ctx: &egui::Context,
+) -> egui::DragValue<'a> {
+    let precision = display_precision(ctx);
+    drag_value.custom_formatter(move |value, _| format!("{value:.precision$}"))
+}
+
+/// Plays a MIDI note on the components: a pressed note adds a sine component at its frequency, or
+/// unmutes the one already added for it, with the velocity as amplitude. A released note mutes its
+/// component. Returns whether the components changed.
+#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+fn play_midi_event(
+    components: &mut Vec<ComponentWrapper>,
+    event: midi::Event,
+    reference_a: f64,
+) -> bool {
+    let note = match event {
+        midi::Event::NoteOn { note, .. } | midi::Event::NoteOff { note } => note,
+    };
+    let name = format!("MIDI {}", note::name(i32::from(note)));
+    let existing = components.iter_mut().find(|c| c.enabled && c.name == name);
+    match (event, existing) {
+        (midi::Event::NoteOn { velocity, .. }, Some(c)) => {
+            if let Component::Sine { amplitude, .. } = &mut c.inner {
+                *amplitude = f64::from(velocity) / 127.0;
+            }
+            c.muted = false;
+            true
+        }
+        (midi::Event::NoteOn { velocity, .. }, None) => {
+            components.push(ComponentWrapper::new(
+                name,
+                Component::Sine {
+                    frequency: note::midi_frequency(i32::from(note), reference_a),
+                    amplitude: f64::from(velocity) / 127.0,
+                    phase: 0.0,
+                },
+            ));
+            true
+        }
+        (midi::Event::NoteOff { .. }, Some(c)) => !std::mem::replace(&mut c.muted, true),
+        (midi::Event::NoteOff { .. }, None) => false,
+    }
+}
+
+/// Formats `bytes` in the largest binary unit that keeps the number at or above 1.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Text field for setting `frequency` by note name, tuned to A4 = `reference_a` Hz.
+///
+/// The note is applied when Enter is pressed. Returns whether `frequency` was set.
+fn note_entry(ui: &mut egui::Ui, frequency: &mut f64, reference_a: f64) -> bool {
+    let mut set = false;
+    ui.horizontal(|ui| {
+        ui.label("Note: ");
+        let id = ui.next_auto_id().with("note");
+        let mut text = ui.data().get_temp::<String>(id).unwrap_or_default();
+        let response = ui
+            .add(
+                egui::TextEdit::singleline(&mut text)
+                    .hint_text("e.g. A4")
+                    .desired_width(60.0),
+            )
+            .on_hover_text(format!(
+                "Type a note such as A4, C#3 or Bb2 and press Enter to set the frequency.\n\
+                 Tuned to A4 = {reference_a} Hz, in equal temperament"
+            ));
+        let parsed = note::frequency(&text, reference_a);
+        if let Some(f) = parsed {
+            let precision = display_precision(ui.ctx());
+            ui.label(format!("{f:.precision$} Hz"));
+        } else if !text.trim().is_empty() {
+            ui.colored_label(ui.visuals().error_fg_color, "Not a note");
+        }
+        if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+            if let Some(f) = parsed {
+                *frequency = f;
+                text.clear();
+                set = true;
+            }
+        }
+        ui.data().insert_temp(id, text);
+    });
+    set
+}
+
+/// The spectrum y-axis `label`, with the quantity the magnitudes stand for under `norm` appended.
+fn normalized_label(label: &str, norm: SpectrumNorm, psd: bool) -> String {
+    if psd || label.is_empty() {
+        label.to_owned()
+    } else {
+        format!("{label} ({})", norm.quantity())
+    }
+}
+
+/// Shows the plot added by `add_plot` with the y axis label above it, and the x axis label below.
+///
+/// Empty labels are skipped.
+fn labelled_plot<R>(
+    ui: &mut egui::Ui,
+    x_label: &str,
+    y_label: &str,
+    add_plot: impl FnOnce(&mut egui::Ui) -> R,
+) -> R {
+    if !y_label.is_empty() {
+        ui.label(egui::RichText::new(y_label).small());
+    }
+    let result = add_plot(ui);
+    if !x_label.is_empty() {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+            ui.label(egui::RichText::new(x_label).small());
+        });
+    }
+
+    result
+}
+
+/// Shows all components in a grid, with their basic parameters editable in place.
+fn component_table<T>(
+    ui: &mut egui::Ui,
+    components: &mut [ComponentWrapper],
+    phase_unit: PhaseUnit,
+    cache: &mut Cache<T>,
+) {
+    let phase_header = format!("Phase [{}]", phase_unit.name().to_lowercase());
+    egui::Grid::new("component_table")
+        .num_columns(6)
+        .striped(true)
+        .show(ui, |ui| {
+            for header in [
+                "Unmuted",
+                "Name",
+                "Type",
+                "Frequency",
+                "Amplitude",
+                &phase_header,
+            ] {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            for c in components.iter_mut() {
+                let mut unmuted = !c.muted;
+                if ui.checkbox(&mut unmuted, "").changed() {
+                    c.muted = !unmuted;
+                    cache.invalidate();
+                }
+                ui.label(&c.name);
+                ui.label(c.inner.name());
+                let [frequency, amplitude, phase] = c.inner.basic_parameters_mut();
+                let mut changed = false;
+                let mut cell =
+                    |ui: &mut egui::Ui,
+                     value: Option<&mut f64>,
+                     drag_value: fn(&mut f64) -> egui::DragValue<'_>| {
+                        match value {
+                            Some(value) => {
+                                let drag_value =
+                                    with_display_precision(drag_value(value), ui.ctx());
+                                changed |= committed(ui.add(drag_value), *value).changed();
+                            }
+                            None => {
+                                ui.label("–");
+                            }
+                        }
+                    };
+                cell(ui, frequency, |f| {
+                    egui::DragValue::new(f)
+                        .clamp_range(1e-2..=f64::MAX)
+                        .suffix(" Hz")
+                });
+                cell(ui, amplitude, |a| {
+                    egui::DragValue::new(a).clamp_range(0.0..=f64::MAX)
+                });
+                match phase {
+                    Some(phase) => {
+                        let (range, suffix) = phase_unit.range();
+                        let mut value = phase_unit.value_of(*phase);
+                        let drag_value = egui::DragValue::new(&mut value)
+                            .clamp_range(range.clone())
+                            .speed((range.end() - range.start()) / 100.0)
+                            .suffix(suffix);
+                        let drag_value = with_display_precision(drag_value, ui.ctx());
+                        if committed(ui.add(drag_value), value).changed() {
+                            *phase = phase_unit.to_turns(value);
+                            changed = true;
+                        }
+                    }
+                    None => {
+                        ui.label("–");
+                    }
+                }
+                if changed {
+                    cache.invalidate();
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// Spectrum of a single component, shown instead of the spectrum of the sum.
+struct IsolatedSpectrum {
+    /// Index of the component in the component list.
+    index: usize,
+    /// Generation of the plot data cache the spectrum was computed for.
+    generation: u64,
+    spectrum: spectrum::Spectrum,
+}
+
+impl SizeBytes for IsolatedSpectrum {
+    fn size_bytes(&self) -> usize {
+        self.spectrum.size_bytes()
+    }
+}
+
+/// Two components to correlate or convolve, by their index in the component list.
+#[derive(Clone, Copy, PartialEq)]
+struct CorrelationSetup {
+    operation: correlation::Operation,
+    pair: [usize; 2],
+}
+
+/// Correlation or convolution of the components in `setup`, as `[lag, value]` points with
+/// the lag in seconds, reduced to the plot point limit.
+struct CorrelationPlot {
+    setup: CorrelationSetup,
+    /// Generation of the plot data cache the points were computed for.
+    generation: u64,
+    points: Vec<[f64; 2]>,
+}
+
+impl SizeBytes for CorrelationPlot {
+    fn size_bytes(&self) -> usize {
+        self.points.size_bytes()
+    }
+}
+
+/// Zoom and pan of a plot, kept independently of the data shown in it.
+#[derive(Default)]
+struct PlotView {
+    /// Bounds the user zoomed or panned to, or `None` to fit the plot to its data.
+    bounds: Option<egui::plot::PlotBounds>,
+    /// Whether the plot should drop its own view state on the next frame.
+    reset_pending: bool,
+}
+
+impl PlotView {
+    /// Goes back to fitting the plot to its data.
+    fn reset(&mut self) {
+        self.bounds = None;
+        self.reset_pending = true;
+    }
+
+    /// Sets up `plot` to show the stored view.
+    fn apply(&mut self, mut plot: egui::plot::Plot) -> egui::plot::Plot {
+        if std::mem::take(&mut self.reset_pending) {
+            plot = plot.reset();
+        }
+        match self.bounds {
+            Some(bounds) => plot
+                .include_x(bounds.min()[0])
+                .include_x(bounds.max()[0])
+                .include_y(bounds.min()[1])
+                .include_y(bounds.max()[1]),
+            None => plot,
+        }
+    }
+
+    /// Stores the bounds once the user has started zooming or panning.
+    fn record(&mut self, plot_ui: &egui::plot::PlotUi) {
+        let interacting = plot_ui.plot_hovered() && {
+            let input = plot_ui.ctx().input();
+            plot_ui.pointer_coordinate_drag_delta() != egui::Vec2::ZERO
+                || input.scroll_delta != egui::Vec2::ZERO
+                || input.zoom_delta_2d() != egui::Vec2::splat(1.0)
+        };
+        // The bounds lag a frame behind, so keep updating them after the interaction.
+        if interacting || self.bounds.is_some() {
+            self.bounds = Some(plot_ui.plot_bounds());
+        }
+    }
+
+    /// Double clicking the plot resets it, same as the plot does on its own.
+    fn handle_response(&mut self, response: &egui::Response) {
+        if response.double_clicked() {
+            self.reset();
+        }
+    }
+}
+
+/// Two markers placed by clicking a plot, to measure the distance between them.
+#[derive(Default)]
+struct Markers {
+    /// Whether clicking the plot places markers.
+    active: bool,
+    /// Positions of the markers placed so far, in data units. At most two.
+    positions: Vec<f64>,
+}
+
+impl Markers {
+    /// Shows the controls for turning the markers on, and removing them.
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.active, "📏 Markers").on_hover_text(
+            "Click the plot to place two markers, and read the distance between them. \
+             A third click starts over.",
+        );
+        if ui
+            .add_enabled(
+                !self.positions.is_empty(),
+                egui::Button::new("Clear markers"),
+            )
+            .clicked()
+        {
+            self.positions.clear();
+        }
+    }
+
+    /// Places a marker where the plot is clicked, if markers are on. `to_data` maps plot x
+    /// coordinates to data units.
+    fn handle_clicks(&mut self, plot_ui: &egui::plot::PlotUi, to_data: impl Fn(f64) -> f64) {
+        if !self.active || !plot_ui.plot_clicked() {
+            return;
+        }
+        if let Some(p) = plot_ui.pointer_coordinate() {
+            if self.positions.len() == 2 {
+                self.positions.clear();
+            }
+            self.positions.push(to_data(p.x));
+        }
+    }
+
+    /// Draws the markers. `to_plot` maps data units to plot x coordinates, if they are shown.
+    fn show(&self, plot_ui: &mut egui::plot::PlotUi, to_plot: impl Fn(f64) -> Option<f64>) {
+        for (position, name) in self.positions.iter().zip(["Marker A", "Marker B"]) {
+            if let Some(x) = to_plot(*position) {
+                plot_ui.vline(egui::plot::VLine::new(x).color(MARKER_COLOR).name(name));
+            }
+        }
+    }
+
+    /// Distance between the two markers, once both are placed.
+    fn span(&self) -> Option<f64> {
+        match self.positions[..] {
+            [a, b] => Some((b - a).abs()),
+            _ => None,
+        }
+    }
+}
+
+/// Where the pointer is on one of the plots, so that the others can mark the matching position.
+#[derive(Clone, Copy)]
+enum LinkedHover {
+    /// Time on the waveform plot.
+    Time(f64),
+    /// Frequency on the spectrum plot.
+    Frequency(f64),
+    /// Time and frequency on the spectrogram.
+    TimeFrequency(f64, f64),
+}
+
+impl LinkedHover {
+    /// Times to mark on the waveform plot, which shows `duration` seconds: the whole periods of
+    /// a hovered frequency, or the time hovered on the spectrogram.
+    fn times(self, duration: f64) -> Vec<f64> {
+        match self {
+            LinkedHover::Frequency(f) if f > 0.0 => (1..=MAX_PERIOD_LINES)
+                .map(|k| k as f64 / f)
+                .take_while(|t| *t <= duration)
+                .collect(),
+            LinkedHover::TimeFrequency(t, _) => vec![t],
+            _ => vec![],
+        }
+    }
+
+    /// Frequency to mark on the spectrum plot: the one whose period is the hovered time, or the
+    /// frequency hovered on the spectrogram.
+    fn frequency(self) -> Option<f64> {
+        match self {
+            LinkedHover::Time(t) if t > 0.0 => Some(1.0 / t),
+            LinkedHover::TimeFrequency(_, f) => Some(f),
+            _ => None,
+        }
+    }
+}
+
+/// Maps frequencies onto the x axis of the spectrum plots.
+#[derive(Clone, Copy)]
+struct FrequencyAxis {
+    log: bool,
+}
+
+impl FrequencyAxis {
+    /// Position of frequency `f` on the axis, or `None` if it can't be shown (`f <= 0` on a log axis).
+    fn x(self, f: f64) -> Option<f64> {
+        if !self.log {
+            Some(f)
+        } else if f > 0.0 {
+            Some(f.log10())
+        } else {
+            None
+        }
+    }
+
+    /// Frequency at position `x` on the axis.
+    fn frequency(self, x: f64) -> f64 {
+        if self.log {
+            10_f64.powf(x)
+        } else {
+            x
+        }
+    }
+
+    /// Maps the frequencies of `[frequency, value]` points onto the axis.
+    fn points(self, points: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        points
+            .iter()
+            .filter_map(|[f, y]| self.x(*f).map(|x| [x, *y]))
+            .collect()
+    }
+
+    /// Tick label for position `x` on a log axis.
+    fn log_label(x: f64, _range: &std::ops::RangeInclusive<f64>) -> String {
+        format!("{} Hz", egui::emath::round_to_decimals(10_f64.powf(x), 5))
+    }
+}
+
+/// Color theme of the app.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Theme {
+    /// Follow the theme of the operating system or browser, if known.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    fn name(self) -> &'static str {
+        match self {
+            Theme::System => "Follow system",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+
+    /// Visuals for this theme, given the system theme, if known.
+    fn visuals(self, system_theme: Option<eframe::Theme>) -> egui::Visuals {
+        match self {
+            Theme::System => system_theme.unwrap_or(eframe::Theme::Dark).egui_visuals(),
+            Theme::Light => egui::Visuals::light(),
+            Theme::Dark => egui::Visuals::dark(),
+        }
+    }
+}
+
+/// Style of a plot line.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    const ALL: [LineStyle; 3] = [LineStyle::Solid, LineStyle::Dashed, LineStyle::Dotted];
+
+    fn name(self) -> &'static str {
+        match self {
+            LineStyle::Solid => "Solid",
+            LineStyle::Dashed => "Dashed",
+            LineStyle::Dotted => "Dotted",
+        }
+    }
+
+    fn to_egui(self) -> egui::plot::LineStyle {
+        match self {
+            LineStyle::Solid => egui::plot::LineStyle::Solid,
+            LineStyle::Dashed => egui::plot::LineStyle::dashed_dense(),
+            LineStyle::Dotted => egui::plot::LineStyle::dotted_dense(),
+        }
+    }
+}
+
+/// Look of a plot line. Only affects drawing, not the data.
+#[derive(Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct LineSettings {
+    width: f32,
+    style: LineStyle,
+}
+
+impl Default for LineSettings {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            style: LineStyle::default(),
+        }
+    }
+}
+
+impl LineSettings {
+    /// Shows the controls for the settings, labelled with `label`.
+    fn show(&mut self, ui: &mut egui::Ui, label: &str) {
+        ui.horizontal(|ui| {
+            ui.label(format!("{label}:"));
+            ui.add(
+                egui::DragValue::new(&mut self.width)
+                    .clamp_range(0.5..=10.0)
+                    .speed(0.05)
+                    .suffix(" px"),
+            );
+            egui::ComboBox::from_id_source(label)
+                .selected_text(self.style.name())
+                .show_ui(ui, |ui| {
+                    for style in LineStyle::ALL {
+                        ui.selectable_value(&mut self.style, style, style.name());
+                    }
+                });
+        });
+    }
+
+    fn apply(self, line: egui::plot::Line) -> egui::plot::Line {
+        line.width(self.width).style(self.style.to_egui())
+    }
+}
+
+/// Range and grid of a plot's axes. Only affects drawing, not the data.
+#[derive(Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct AxisSettings {
+    /// Range the y axis always shows, instead of just fitting the data. It still grows to fit
+    /// data beyond it.
+    y_range: Option<[f64; 2]>,
+    grid: bool,
+    /// Draw the finest grid lines, between the labelled ones.
+    minor_grid: bool,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            y_range: None,
+            grid: true,
+            minor_grid: true,
+        }
+    }
+}
+
+impl AxisSettings {
+    /// Shows the controls for the settings, labelled with `label`. Fixing the range starts
+    /// out at `default_range`.
+    fn show(&mut self, ui: &mut egui::Ui, label: &str, default_range: [f64; 2]) {
+        ui.horizontal(|ui| {
+            ui.label(format!("{label}:"));
+            let mut fixed = self.y_range.is_some();
+            if ui
+                .checkbox(&mut fixed, "Fix y range")
+                .on_hover_text("Keep the y axis from following the data as it changes")
+                .changed()
+            {
+                self.y_range = fixed.then_some(default_range);
+            }
+            if let Some([min, max]) = &mut self.y_range {
+                ui.add(egui::DragValue::new(min).speed(0.01));
+                ui.label("to");
+                ui.add(egui::DragValue::new(max).speed(0.01));
+            }
+            ui.checkbox(&mut self.grid, "Grid")
+                .on_hover_text("Grid lines, along with the axis labels at them");
+            ui.add_enabled(
+                self.grid,
+                egui::Checkbox::new(&mut self.minor_grid, "Minor grid"),
+            );
+        });
+    }
+
+    fn apply(self, mut plot: egui::plot::Plot) -> egui::plot::Plot {
+        if let Some([min, max]) = self.y_range {
+            plot = plot.include_y(min).include_y(max);
+        }
+        if !self.grid {
+            plot = plot.x_grid_spacer(|_| vec![]).y_grid_spacer(|_| vec![]);
+        } else if !self.minor_grid {
+            plot = plot.x_grid_spacer(major_grid).y_grid_spacer(major_grid);
+        }
+        plot
+    }
+}
 
-            #[allow(clippy::cast_precision_loss)]
-            let points = egui::plot::PlotPoints::from(pd.waveform.clone());
-            let line = egui::plot::Line::new(points);
-            egui::plot::Plot::new("wf_plot")
-                .view_aspect(4.0)
-                .show(ui, |plot_ui| plot_ui.line(line));
+/// Range a fixed spectrum y axis starts out at, in decibels if `db`.
+fn spectrum_range(db: bool) -> [f64; 2] {
+    if db {
+        [-120.0, 0.0]
+    } else {
+        [0.0, 1.5]
+    }
+}
 
-            ui.heading("Spectrum");
+/// Marks of egui's default grid, without the finest ones.
+fn major_grid(input: egui::plot::GridInput) -> Vec<egui::plot::GridMark> {
+    let marks = egui::plot::log_grid_spacer(10)(input);
+    let finest = marks
+        .iter()
+        .map(|mark| mark.step_size)
+        .fold(f64::INFINITY, f64::min);
+    marks
+        .into_iter()
+        .filter(|mark| mark.step_size > finest)
+        .collect()
+}
 
-            #[allow(clippy::cast_precision_loss)]
-            let points = egui::plot::PlotPoints::from(pd.spectrum.clone());
-            let line = egui::plot::Line::new(points);
-            egui::plot::Plot::new("spectrum_plot")
-                .view_aspect(4.0)
-                .legend(egui::plot::Legend::default())
-                .show(ui, |plot_ui| {
-                    plot_ui.line(line);
-                    for c in components.iter() {
-                        plot_ui.vline(
-                            egui::plot::VLine::new(c.inner.frequency()).name(c.name.clone()),
-                        );
+/// How a kind of parameter is stepped by dragging it, or with the arrow keys while it has
+/// keyboard focus.
+#[derive(Clone, Copy)]
+struct Step {
+    /// Change per point dragged, and per arrow key press.
+    fine: f64,
+    /// Change per arrow key press with Shift held. Should be a multiple of `fine`, as drag
+    /// values can only step by that.
+    coarse: f64,
+}
+
+impl Step {
+    const FREQUENCY: Self = Self {
+        fine: 1.0,
+        coarse: 100.0,
+    };
+    const AMPLITUDE: Self = Self {
+        fine: 0.01,
+        coarse: 0.1,
+    };
+    const DECIBELS: Self = Self {
+        fine: 0.1,
+        coarse: 1.0,
+    };
+    const DURATION: Self = Self {
+        fine: 0.01,
+        coarse: 0.1,
+    };
+    const CENTS: Self = Self {
+        fine: 1.0,
+        coarse: 100.0,
+    };
+    const DUTY: Self = Self {
+        fine: 0.01,
+        coarse: 0.1,
+    };
+
+    /// Adds `drag_value`, stepping by `fine`, and by `coarse` with Shift+Up/Down.
+    ///
+    /// The value is shown with [`display_precision`] decimal places.
+    fn drag_value(self, ui: &mut egui::Ui, drag_value: egui::DragValue<'_>) -> egui::Response {
+        let drag_value = with_display_precision(drag_value, ui.ctx());
+        // The drag value is the next widget, and only steps on unmodified Up and Down, so
+        // Shift+Up and Shift+Down are passed on to it as that many unmodified presses.
+        let id = ui.next_auto_id();
+        if ui.memory().has_focus(id) {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let repeats = (self.coarse / self.fine).round() as usize;
+            let mut input = ui.input_mut();
+            input.events = std::mem::take(&mut input.events)
+                .into_iter()
+                .flat_map(|event| match event {
+                    egui::Event::Key {
+                        key: key @ (egui::Key::ArrowUp | egui::Key::ArrowDown),
+                        pressed: true,
+                        modifiers,
+                    } if modifiers.shift_only() => {
+                        let unmodified = egui::Event::Key {
+                            key,
+                            pressed: true,
+                            modifiers: egui::Modifiers::NONE,
+                        };
+                        vec![unmodified; repeats]
                     }
-                });
-        });
+                    event => vec![event],
+                })
+                .collect();
+        }
+        ui.add(drag_value.speed(self.fine))
+    }
 
-        while let Some(i) = components.iter().position(|c| !c.enabled) {
-            components.remove(i);
-            plot_data_cache.invalidate();
+    /// Adds `slider`, stepping by `fine` with Left/Right, and by `coarse` with Shift+Left/Right.
+    ///
+    /// Returns the slider's response, and the change the Shift presses add on top of the fine
+    /// step the slider already took for them.
+    fn slider(self, ui: &mut egui::Ui, slider: egui::Slider<'_>) -> (egui::Response, f64) {
+        let response = ui.add(slider.step_by(self.fine));
+        let mut change = 0.0;
+        if response.has_focus() {
+            let input = ui.input();
+            for event in &input.events {
+                if let egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                } = event
+                {
+                    let sign = match key {
+                        egui::Key::ArrowRight => 1.0,
+                        egui::Key::ArrowLeft => -1.0,
+                        _ => continue,
+                    };
+                    if modifiers.shift_only() {
+                        change += sign * (self.coarse - self.fine);
+                    }
+                }
+            }
         }
+        (response, change)
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// Unit in which phases are shown and edited. They are always stored in turns.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum PhaseUnit {
+    /// Fractions of a period, from 0 to 1.
+    #[default]
+    Turns,
+    /// From -π to π.
+    Radians,
+    /// From -180° to 180°.
+    Degrees,
+}
+
+impl PhaseUnit {
+    const ALL: [PhaseUnit; 3] = [PhaseUnit::Turns, PhaseUnit::Radians, PhaseUnit::Degrees];
+
+    fn name(self) -> &'static str {
+        match self {
+            PhaseUnit::Turns => "Turns",
+            PhaseUnit::Radians => "Radians",
+            PhaseUnit::Degrees => "Degrees",
+        }
+    }
+
+    /// Converts a phase in turns to this unit.
+    fn value_of(self, turns: f64) -> f64 {
+        // The bipolar units are centered on zero.
+        let centered = turns - turns.round();
+        match self {
+            PhaseUnit::Turns => turns,
+            PhaseUnit::Radians => centered * std::f64::consts::TAU,
+            PhaseUnit::Degrees => centered * 360.0,
+        }
+    }
+
+    /// Converts a phase in this unit to turns, in `[0, 1)`.
+    fn to_turns(self, value: f64) -> f64 {
+        let turns = match self {
+            PhaseUnit::Turns => return value,
+            PhaseUnit::Radians => value / std::f64::consts::TAU,
+            PhaseUnit::Degrees => value / 360.0,
+        };
+        turns.rem_euclid(1.0)
+    }
+
+    /// Range of phases in this unit, and the suffix they are shown with.
+    fn range(self) -> (std::ops::RangeInclusive<f64>, &'static str) {
+        match self {
+            PhaseUnit::Turns => (0.0..=1.0, ""),
+            PhaseUnit::Radians => (-std::f64::consts::PI..=std::f64::consts::PI, " rad"),
+            PhaseUnit::Degrees => (-180.0..=180.0, "°"),
+        }
+    }
+
+    /// Shows a slider editing `phase`, which is in turns, in this unit.
+    fn slider(self, ui: &mut egui::Ui, phase: &mut f64) -> egui::Response {
+        let (range, suffix) = self.range();
+        let mut value = self.value_of(*phase);
+        let (mut response, change) = self.step().slider(
+            ui,
+            egui::Slider::new(&mut value, range.clone())
+                .prefix("φ: ")
+                .suffix(suffix),
+        );
+        if change != 0.0 {
+            value = (value + change).clamp(*range.start(), *range.end());
+            response.mark_changed();
+        }
+        if response.changed() {
+            *phase = self.to_turns(value);
+        }
+        response
+    }
+
+    /// Steps of the phase slider. Shift steps by an eighth of a turn in every unit.
+    fn step(self) -> Step {
+        match self {
+            PhaseUnit::Turns => Step {
+                fine: 0.001,
+                coarse: 0.125,
+            },
+            PhaseUnit::Radians => Step {
+                fine: 0.01,
+                coarse: std::f64::consts::FRAC_PI_4,
+            },
+            PhaseUnit::Degrees => Step {
+                fine: 1.0,
+                coarse: 45.0,
+            },
+        }
+    }
+}
+
+/// Unit in which amplitudes are shown and edited. They are always stored as linear factors.
+#[derive(Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum AmplitudeUnit {
+    #[default]
+    Linear,
+    /// Decibels relative to full scale, where an amplitude of 1 is 0 dBFS.
+    Dbfs,
+}
+
+impl AmplitudeUnit {
+    const ALL: [AmplitudeUnit; 2] = [AmplitudeUnit::Linear, AmplitudeUnit::Dbfs];
+
+    /// Lowest level shown in dBFS. Anything at or below it is silence.
+    const DBFS_FLOOR: f64 = -96.0;
+
+    fn name(self) -> &'static str {
+        match self {
+            AmplitudeUnit::Linear => "Linear",
+            AmplitudeUnit::Dbfs => "dBFS",
+        }
+    }
+
+    /// Shows a drag value editing the linear `amplitude` in this unit.
+    fn drag_value(self, ui: &mut egui::Ui, amplitude: &mut f64) -> egui::Response {
+        match self {
+            AmplitudeUnit::Linear => committed(
+                Step::AMPLITUDE.drag_value(
+                    ui,
+                    egui::DragValue::new(amplitude)
+                        .clamp_range(0.0..=f64::MAX)
+                        .prefix("A: "),
+                ),
+                *amplitude,
+            ),
+            AmplitudeUnit::Dbfs => {
+                let mut db = (20.0 * amplitude.log10()).max(Self::DBFS_FLOOR);
+                let response = committed(
+                    Step::DECIBELS.drag_value(
+                        ui,
+                        egui::DragValue::new(&mut db)
+                            .clamp_range(Self::DBFS_FLOOR..=f64::MAX)
+                            .prefix("A: ")
+                            .suffix(" dBFS"),
+                    ),
+                    db,
+                )
+                .on_hover_text(format!("{} dBFS is silence", Self::DBFS_FLOOR));
+                if response.changed() {
+                    *amplitude = if db <= Self::DBFS_FLOOR {
+                        0.0
+                    } else {
+                        10_f64.powf(db / 20.0)
+                    };
+                }
+                response
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 struct ComponentWrapper {
     inner: Component,
     name: String,
+    /// Cleared when the component is removed; it is then dropped at the end of the frame.
     enabled: bool,
+    /// Inactive components are left out of the summed waveform, but stay in the list.
+    #[serde(default = "active_default")]
+    active: bool,
+    /// Excluded from the summed waveform.
+    #[serde(default)]
+    muted: bool,
+    /// When any component is soloed, only soloed components are summed.
+    #[serde(default)]
+    solo: bool,
+    /// Color of the component's own waveform, when individual waveforms are shown.
+    #[serde(default = "random_color")]
+    color: egui::Color32,
+    /// Name of the group this component is listed under, if any.
+    #[serde(default)]
+    group: Option<String>,
+    /// Detune in cents, applied to all of the component's frequencies.
+    #[serde(default)]
+    cents: f64,
+    /// Time the component starts after the beginning of the window, in seconds. It is silent
+    /// before that.
+    #[serde(default)]
+    delay: f64,
+    /// Amplitude envelope over the sample window, if any.
+    #[serde(default)]
+    envelope: Option<Envelope>,
+    /// Frequency changes of linked components are applied to all of them proportionally.
+    #[serde(default)]
+    linked: bool,
+    /// Which of the stereo channels the component plays on.
+    #[serde(default)]
+    channel: Channel,
+}
+
+/// Channel assignment of a component. The signal is stereo as soon as any summed component is on
+/// one channel only.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Channel {
+    #[default]
+    Both,
+    Left,
+    Right,
+}
+
+impl Channel {
+    const ALL: [Channel; 3] = [Channel::Both, Channel::Left, Channel::Right];
+
+    fn name(self) -> &'static str {
+        match self {
+            Channel::Both => "L + R",
+            Channel::Left => "L",
+            Channel::Right => "R",
+        }
+    }
+
+    /// Whether a component on this channel is part of the left channel's signal.
+    fn left(self) -> bool {
+        self != Channel::Right
+    }
+
+    /// Whether a component on this channel is part of the right channel's signal.
+    fn right(self) -> bool {
+        self != Channel::Left
+    }
+}
+
+fn active_default() -> bool {
+    true
+}
+
+/// Picks a random, reasonably saturated color.
+fn random_color() -> egui::Color32 {
+    #[allow(clippy::cast_precision_loss)]
+    let hue = (uuid::Uuid::new_v4().as_u128() % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.85, 0.5, 1.0).into()
+}
+
+/// Picks a random number in `[0, 1)`.
+fn random_unit() -> f64 {
+    // The low bits of a v4 UUID are all random; the version and variant are stored higher up.
+    let bits = uuid::Uuid::new_v4().as_u128() & ((1 << 53) - 1);
+    #[allow(clippy::cast_precision_loss)]
+    let unit = bits as f64 / (1_u64 << 53) as f64;
+    unit
+}
+
+/// A fundamental sine and its harmonics, with amplitudes falling off as `1 / k^rolloff`.
+#[derive(Clone, Copy)]
+struct HarmonicSeries {
+    base_frequency: f64,
+    /// Number of sines, including the fundamental.
+    harmonics: usize,
+    rolloff: f64,
+}
+
+impl Default for HarmonicSeries {
+    fn default() -> Self {
+        Self {
+            base_frequency: 100.0,
+            harmonics: 5,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl HarmonicSeries {
+    fn show(&mut self, ui: &mut egui::Ui) {
+        ui.add(with_display_precision(
+            egui::DragValue::new(&mut self.base_frequency)
+                .clamp_range(1e-2..=f64::MAX)
+                .prefix("Base frequency: ")
+                .suffix(" Hz"),
+            ui.ctx(),
+        ));
+        ui.add(
+            egui::DragValue::new(&mut self.harmonics)
+                .clamp_range(1..=100)
+                .prefix("Harmonics: "),
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.rolloff)
+                .clamp_range(0.0..=10.0)
+                .speed(0.01)
+                .prefix("Rolloff: "),
+        )
+        .on_hover_text("The k-th harmonic gets an amplitude of 1 / k^rolloff");
+    }
+
+    fn components(self) -> impl Iterator<Item = ComponentWrapper> {
+        (1..=self.harmonics).map(move |k| {
+            #[allow(clippy::cast_precision_loss)]
+            let k_f = k as f64;
+            ComponentWrapper::new(
+                format!("Harmonic {k}"),
+                Component::Sine {
+                    frequency: self.base_frequency * k_f,
+                    amplitude: k_f.powf(-self.rolloff),
+                    phase: 0.0,
+                },
+            )
+        })
+    }
+}
+
+/// Current version of the [`Preset`] file format.
+const PRESET_VERSION: u32 = 1;
+
+/// A signal setup, as stored in preset files.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Preset {
+    version: u32,
+    sample_rate: f64,
+    n_samples: usize,
+    components: Vec<ComponentWrapper>,
+}
+
+impl Preset {
+    fn new(sample_rate: f64, n_samples: usize, components: Vec<ComponentWrapper>) -> Self {
+        Self {
+            version: PRESET_VERSION,
+            sample_rate,
+            n_samples,
+            components,
+        }
+    }
+
+    /// Asks the user where to save the preset, and saves it there as JSON.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) -> Result<(), export::Error> {
+        let json = serde_json::to_string_pretty(self)?;
+        export::save_text("preset.json", "application/json", &json)
+    }
+
+    /// Asks the user for a preset file and loads it. Returns `None` if the dialog is cancelled.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Result<Option<Self>, export::Error> {
+        let Some(json) = export::load_text("json")? else {
+            return Ok(None);
+        };
+        let preset: Self = serde_json::from_str(&json)?;
+        if preset.version > PRESET_VERSION {
+            return Err(export::Error::UnsupportedVersion(preset.version));
+        }
+
+        Ok(Some(preset))
+    }
+}
+
+/// A [`Preset`] kept in the app state, under a name.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct NamedPreset {
+    name: String,
+    preset: Preset,
+}
+
+/// A curated signal setup, offered in the "Examples" menu.
+struct Example {
+    name: &'static str,
+    description: &'static str,
+    sample_rate: f64,
+    n_samples: usize,
+    /// Names and components.
+    components: &'static [(&'static str, Component)],
+}
+
+impl Example {
+    fn preset(&self) -> Preset {
+        Preset::new(
+            self.sample_rate,
+            self.n_samples,
+            self.components
+                .iter()
+                .map(|(name, c)| ComponentWrapper::new(*name, c.clone()))
+                .collect(),
+        )
+    }
+}
+
+const EXAMPLES: &[Example] = &[
+    Example {
+        name: "Square wave harmonics",
+        description: "The first odd harmonics of a 100 Hz square wave, at amplitudes falling off \
+                      as 1/k. Deactivate them one by one to see the square wave fall apart.",
+        sample_rate: 8000.0,
+        n_samples: 800,
+        components: &[
+            (
+                "Fundamental",
+                Component::Sine {
+                    frequency: 100.0,
+                    amplitude: 1.0,
+                    phase: 0.0,
+                },
+            ),
+            (
+                "3rd harmonic",
+                Component::Sine {
+                    frequency: 300.0,
+                    amplitude: 1.0 / 3.0,
+                    phase: 0.0,
+                },
+            ),
+            (
+                "5th harmonic",
+                Component::Sine {
+                    frequency: 500.0,
+                    amplitude: 1.0 / 5.0,
+                    phase: 0.0,
+                },
+            ),
+            (
+                "7th harmonic",
+                Component::Sine {
+                    frequency: 700.0,
+                    amplitude: 1.0 / 7.0,
+                    phase: 0.0,
+                },
+            ),
+            (
+                "9th harmonic",
+                Component::Sine {
+                    frequency: 900.0,
+                    amplitude: 1.0 / 9.0,
+                    phase: 0.0,
+                },
+            ),
+        ],
+    },
+    Example {
+        name: "Beating sines",
+        description: "Two sines 4 Hz apart. Their sum swells and fades 4 times a second, and the \
+                      spectrum only tells them apart with a long enough window.",
+        sample_rate: 8000.0,
+        n_samples: 8000,
+        components: &[
+            (
+                "440 Hz",
+                Component::Sine {
+                    frequency: 440.0,
+                    amplitude: 0.5,
+                    phase: 0.0,
+                },
+            ),
+            (
+                "444 Hz",
+                Component::Sine {
+                    frequency: 444.0,
+                    amplitude: 0.5,
+                    phase: 0.0,
+                },
+            ),
+        ],
+    },
+    Example {
+        name: "Aliasing demo",
+        description: "A 900 Hz sine sampled at 1 kHz shows up at 100 Hz, and the harmonics of a \
+                      square wave fold back below the Nyquist frequency. Turn on band limiting \
+                      to remove the folded harmonics.",
+        sample_rate: 1000.0,
+        n_samples: 1000,
+        components: &[
+            (
+                "900 Hz sine",
+                Component::Sine {
+                    frequency: 900.0,
+                    amplitude: 1.0,
+                    phase: 0.0,
+                },
+            ),
+            (
+                "130 Hz square",
+                Component::Square {
+                    frequency: 130.0,
+                    amplitude: 0.5,
+                    phase: 0.0,
+                },
+            ),
+        ],
+    },
+    Example {
+        name: "Chirp spectrogram",
+        description: "A sweep from 100 Hz to 3 kHz every second. Turn on the spectrogram to see \
+                      its frequency rise over time.",
+        sample_rate: 8000.0,
+        n_samples: 16000,
+        components: &[(
+            "Chirp",
+            Component::Chirp {
+                f_start: 100.0,
+                f_end: 3000.0,
+                amplitude: 1.0,
+                duration: 1.0,
+            },
+        )],
+    },
+];
+
+/// A change to the components list requested from within a single component's UI.
+enum ComponentAction {
+    /// Swap places with the component at the given index.
+    SwapWith(usize),
+    /// Append a copy of this component.
+    Duplicate,
+    /// Change the number of samples so the window holds this many whole periods of the
+    /// component.
+    SnapWindow(usize),
+    /// Scale the frequencies of the other linked components by this factor, as this one's
+    /// frequency just changed by it.
+    ScaleLinked(f64),
 }
 
 impl ComponentWrapper {
-    pub fn show<T>(&mut self, ui: &mut egui::Ui, sampling_frequency: f64, cache: &mut Cache<T>) {
+    pub fn new(name: impl Into<String>, inner: Component) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            enabled: true,
+            active: true,
+            muted: false,
+            solo: false,
+            color: random_color(),
+            group: None,
+            cents: 0.0,
+            delay: 0.0,
+            envelope: None,
+            linked: false,
+            channel: Channel::default(),
+        }
+    }
+
+    /// The component with its frequencies detuned by `cents`.
+    pub fn detuned(&self) -> Component {
+        let mut inner = self.inner.clone();
+        inner.scale_frequencies(2_f64.powf(self.cents / 1200.0));
+        inner
+    }
+
+    /// Settings of this component that [`Component::to_code`] leaves out, as they aren't part of
+    /// a `wavegen` function. `band_limited` is whether square and sawtooth waves are band limited.
+    pub fn code_omissions(&self, band_limited: bool) -> Vec<&'static str> {
+        let mut omitted = vec![];
+        if band_limited && self.inner.has_harmonics_to_limit() {
+            omitted.push("band limiting");
+        }
+        if self.envelope.is_some() {
+            omitted.push("envelope");
+        }
+        if self.delay != 0.0 {
+            omitted.push("delay");
+        }
+        if self.channel != Channel::Both {
+            omitted.push("channel");
+        }
+        omitted
+    }
+
+    /// The first `n_samples` samples of the detuned component, band limited to `band_limit`,
+    /// shaped by its envelope and delayed.
+    pub fn samples(&self, sample_rate: f64, n_samples: usize, band_limit: Option<f64>) -> Vec<f64> {
+        let mut samples: Vec<_> = Waveform::<f64, f64>::with_components(
+            sample_rate,
+            vec![self.detuned().build(band_limit)],
+        )
+        .iter()
+        .take(n_samples)
+        .collect();
+        if let Some(envelope) = self.envelope {
+            #[allow(clippy::cast_precision_loss)]
+            let duration = n_samples as f64 / sample_rate;
+            for (i, s) in samples.iter_mut().enumerate() {
+                #[allow(clippy::cast_precision_loss)]
+                let t = i as f64 / sample_rate;
+                *s *= envelope.value(t, duration);
+            }
+        }
+        // Rounded to whole samples.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let shift = ((self.delay * sample_rate).round() as usize).min(samples.len());
+        samples.rotate_right(shift);
+        samples[..shift].fill(0.0);
+        samples
+    }
+
+    /// Shows the component's phase and amplitude as a vector in the unit circle, if it has them.
+    fn show_phasor(&mut self, ui: &mut egui::Ui) {
+        let [_, Some(amplitude), Some(phase)] = self.inner.basic_parameters_mut() else {
+            return;
+        };
+        let (amplitude, angle) = (*amplitude, std::f64::consts::TAU * *phase);
+        ui.collapsing("Phasor", |ui| {
+            let circle = egui::plot::PlotPoints::from_parametric_callback(
+                |t| (t.cos(), t.sin()),
+                0.0..=std::f64::consts::TAU,
+                64,
+            );
+            let tip = [amplitude * angle.cos(), amplitude * angle.sin()];
+            egui::plot::Plot::new("phasor")
+                .width(120.0)
+                .height(120.0)
+                .data_aspect(1.0)
+                .include_x(-1.1)
+                .include_x(1.1)
+                .include_y(-1.1)
+                .include_y(1.1)
+                .show_axes([false, false])
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .allow_boxed_zoom(false)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(egui::plot::Line::new(circle).color(egui::Color32::GRAY));
+                    plot_ui.arrows(
+                        egui::plot::Arrows::new(vec![[0.0, 0.0]], vec![tip]).color(self.color),
+                    );
+                });
+        });
+    }
+
+    /// Shows the editor of the component's envelope.
+    fn show_envelope<T>(&mut self, ui: &mut egui::Ui, cache: &mut Cache<T>) {
+        ui.collapsing("Envelope", |ui| {
+            let mut enabled = self.envelope.is_some();
+            if ui
+                .checkbox(&mut enabled, "ADSR envelope")
+                .on_hover_text(
+                    "Shape the component's amplitude over the sample window: attack, decay, \
+                     sustain level and release",
+                )
+                .changed()
+            {
+                self.envelope = enabled.then(Envelope::default);
+                cache.invalidate();
+            }
+            let Some(envelope) = &mut self.envelope else {
+                return;
+            };
+            ui.horizontal(|ui| {
+                let time = |ui: &mut egui::Ui, value: &mut f64, prefix: &str| {
+                    committed(
+                        Step::DURATION.drag_value(
+                            ui,
+                            egui::DragValue::new(value)
+                                .clamp_range(0.0..=f64::MAX)
+                                .prefix(prefix)
+                                .suffix(" s"),
+                        ),
+                        *value,
+                    )
+                    .changed()
+                };
+                if time(ui, &mut envelope.attack, "A: ")
+                    | time(ui, &mut envelope.decay, "D: ")
+                    | committed(
+                        Step::AMPLITUDE.drag_value(
+                            ui,
+                            egui::DragValue::new(&mut envelope.sustain)
+                                .clamp_range(0.0..=1.0)
+                                .prefix("S: "),
+                        ),
+                        envelope.sustain,
+                    )
+                    .changed()
+                    | time(ui, &mut envelope.release, "R: ")
+                {
+                    cache.invalidate();
+                }
+            });
+        });
+    }
+
+    /// Whether this component contributes to the summed waveform.
+    pub fn is_audible(&self, any_solo: bool, disabled_groups: &BTreeSet<String>) -> bool {
+        if self
+            .group
+            .as_ref()
+            .map_or(false, |g| disabled_groups.contains(g))
+        {
+            false
+        } else if any_solo {
+            self.solo
+        } else {
+            !self.muted
+        }
+    }
+
+    /// Shows the component's controls.
+    ///
+    /// `max_frequency` is the highest frequency shown in the spectrum; components above it are
+    /// flagged with a warning.
+    /// `window_duration` is the length of the sample window, in seconds.
+    /// `reference_a`, next to the units, is the pitch of A4 that note names are tuned to.
+    /// `neighbours` are the indices of the previous and next visible components, if any.
+    pub fn show<T>(
+        &mut self,
+        ui: &mut egui::Ui,
+        max_frequency: f64,
+        window_duration: f64,
+        (phase_unit, amplitude_unit, reference_a): (PhaseUnit, AmplitudeUnit, f64),
+        neighbours: (Option<usize>, Option<usize>),
+        cache: &mut Cache<T>,
+    ) -> Option<ComponentAction> {
+        let mut action = None;
         ui.horizontal(|ui| {
             let label = ui.label("Name: ");
             ui.text_edit_singleline(&mut self.name)
                 .labelled_by(label.id).on_hover_text("Name of this component.\n\
-                                                      This is currently only used for spectrum marker");
+                                                      It is shown on the spectrum markers and the waveform legend");
+            ui.color_edit_button_srgba(&mut self.color)
+                .on_hover_text("Color of this component's waveform");
+        });
+        ui.horizontal(|ui| {
+            let label = ui.label("Group: ");
+            // Edited in a buffer and applied when done, since a new group moves the card to
+            // another section, where the text field would be a new widget without focus.
+            let id = ui.next_auto_id().with("group");
+            let buffer = ui.data().get_temp::<String>(id);
+            let mut group = buffer.unwrap_or_else(|| self.group.clone().unwrap_or_default());
+            let response = ui
+                .text_edit_singleline(&mut group)
+                .labelled_by(label.id)
+                .on_hover_text(
+                    "Components with the same group are listed together.\n\
+                     Leave empty for no group. Applied on Enter or when leaving the field",
+                );
+            if response.lost_focus() {
+                ui.data().remove::<String>(id);
+                let group = (!group.trim().is_empty()).then_some(group);
+                if group != self.group {
+                    self.group = group;
+                    // The component may have moved into or out of a disabled group.
+                    cache.invalidate();
+                }
+            } else if response.has_focus() {
+                ui.data().insert_temp(id, group);
+            }
         });
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.active, "Active")
+                .on_hover_text("Inactive components are left out of the sum, but not removed")
+                .changed()
+                | ui.checkbox(&mut self.muted, "Mute").changed()
+                | ui.checkbox(&mut self.solo, "Solo").changed()
+            {
+                cache.invalidate();
+            }
+            let channel = self.channel;
+            egui::ComboBox::from_id_source("channel")
+                .selected_text(self.channel.name())
+                .width(50.0)
+                .show_ui(ui, |ui| {
+                    for c in Channel::ALL {
+                        ui.selectable_value(&mut self.channel, c, c.name());
+                    }
+                })
+                .response
+                .on_hover_text("Stereo channel the component plays on");
+            if self.channel != channel {
+                cache.invalidate();
+            }
+            if self.inner.is_periodic() {
+                ui.checkbox(&mut self.linked, "🔗 Link").on_hover_text(
+                    "Changing the frequency of a linked component scales the frequencies of \
+                     all other linked components by the same ratio",
+                );
+            }
+        });
+        let frequency = self.inner.frequency();
         ui.vertical(|ui| {
-            self.inner.show(ui, cache);
-            if self.inner.frequency() * FMAX_SCALE > sampling_frequency {
+            self.inner.show(ui, phase_unit, amplitude_unit, cache);
+            if self.inner.is_periodic()
+                && committed(
+                    Step::CENTS.drag_value(
+                        ui,
+                        egui::DragValue::new(&mut self.cents)
+                            .clamp_range(-2400.0..=2400.0)
+                            .prefix("Detune: ")
+                            .suffix(" ¢"),
+                    ),
+                    self.cents,
+                )
+                .on_hover_text("Shifts all frequencies by this many cents (1/100 semitone)")
+                .changed()
+            {
+                cache.invalidate();
+            }
+            if committed(
+                Step::DURATION.drag_value(
+                    ui,
+                    egui::DragValue::new(&mut self.delay)
+                        .clamp_range(0.0..=f64::MAX)
+                        .prefix("Delay: ")
+                        .suffix(" s"),
+                ),
+                self.delay,
+            )
+            .on_hover_text(
+                "Starts the component this long after the beginning of the window.\n\
+                 Unlike the phase, this is absolute time, the same for any frequency",
+            )
+            .changed()
+            {
+                cache.invalidate();
+            }
+            if let [Some(frequency), ..] = self.inner.basic_parameters_mut() {
+                if note_entry(ui, frequency, reference_a) {
+                    cache.invalidate();
+                }
+            }
+            self.show_phasor(ui);
+            self.show_envelope(ui, cache);
+            if self.inner.is_periodic() {
+                let periods = window_duration * self.detuned().frequency();
+                // Snapping to zero periods would leave no samples at all.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let whole = periods.round().max(1.0) as usize;
+                ui.horizontal(|ui| {
+                    let precision = display_precision(ui.ctx());
+                    ui.label(format!("Window: {periods:.precision$} periods"));
+                    if ui
+                        .button(format!("Snap to {whole}"))
+                        .on_hover_text(
+                            "Change the number of samples so the window holds a whole number \
+                             of periods,\nwhich keeps the component's spectrum free of leakage.\n\
+                             The FFT size is set to the number of samples too.",
+                        )
+                        .clicked()
+                    {
+                        action = Some(ComponentAction::SnapWindow(whole));
+                    }
+                });
+            }
+            if self.inner.is_periodic() && self.detuned().frequency_range().1 > max_frequency {
                 ui.label(
-                    egui::RichText::new("⚠ Above Nyquist frequency ⚠")
+                    egui::RichText::new("⚠ Above the highest analyzed frequency ⚠")
                         .color(ui.visuals().warn_fg_color),
+                )
+                .on_hover_text(
+                    "Part of this component is above the top of the spectrum, which is set by \
+                     the sample rate, decimation and Fmax",
                 );
             }
-            if ui.button("❌ Remove").clicked() {
-                self.enabled = false;
-            }
+            ui.horizontal(|ui| {
+                let (previous, next) = neighbours;
+                if ui
+                    .add_enabled(previous.is_some(), egui::Button::new("⬆"))
+                    .on_hover_text("Move up")
+                    .clicked()
+                {
+                    action = previous.map(ComponentAction::SwapWith);
+                }
+                if ui
+                    .add_enabled(next.is_some(), egui::Button::new("⬇"))
+                    .on_hover_text("Move down")
+                    .clicked()
+                {
+                    action = next.map(ComponentAction::SwapWith);
+                }
+                if ui.button("⎘ Duplicate").clicked() {
+                    action = Some(ComponentAction::Duplicate);
+                }
+                let code = self.detuned().to_code().map(|code| {
+                    let omitted = self.code_omissions(false);
+                    if omitted.is_empty() {
+                        code
+                    } else {
+                        format!("// not included: {}\n{code}", omitted.join(", "))
+                    }
+                });
+                if ui
+                    .add_enabled(code.is_some(), egui::Button::new("📋 Copy as code"))
+                    .on_hover_text(
+                        "Copy the component as a wavegen function, to paste into Rust code.\n\
+                         The envelope, delay and channel are left out and noted in a comment. \
+                         Band limiting is left out too",
+                    )
+                    .on_disabled_hover_text("This component has no wavegen equivalent")
+                    .clicked()
+                {
+                    ui.output().copied_text = code.unwrap_or_default();
+                }
+                let [frequency, amplitude, phase] = self.inner.basic_parameters_mut();
+                if ui
+                    .add_enabled(amplitude.is_some(), egui::Button::new("🎲 Randomize"))
+                    .on_hover_text("Pick a random frequency, amplitude and phase")
+                    .clicked()
+                {
+                    if let Some(f) = frequency {
+                        *f = (random_unit() * max_frequency).max(1e-2);
+                    }
+                    if let Some(a) = amplitude {
+                        *a = random_unit();
+                    }
+                    if let Some(p) = phase {
+                        *p = random_unit();
+                    }
+                    cache.invalidate();
+                }
+                if ui.button("❌ Remove").clicked() {
+                    self.enabled = false;
+                }
+            });
         });
+        // Checked after all of the edits above. Scaling to or from zero would leave the linked
+        // frequencies stuck at zero.
+        let new_frequency = self.inner.frequency();
+        #[allow(clippy::float_cmp)]
+        if self.linked
+            && action.is_none()
+            && frequency > 0.0
+            && new_frequency > 0.0
+            && new_frequency != frequency
+        {
+            action = Some(ComponentAction::ScaleLinked(new_frequency / frequency));
+        }
+
+        action
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// A type of component that can be added from the palette.
+#[derive(Clone, Copy)]
+enum ComponentKind {
+    Sine,
+    Square,
+    Sawtooth,
+    Triangle,
+    Pulse,
+    Dc,
+    Chirp,
+    Modulated,
+    Noise,
+}
+
+impl ComponentKind {
+    /// All kinds, in the order they are listed in the palette.
+    const ALL: &'static [ComponentKind] = &[
+        ComponentKind::Sine,
+        ComponentKind::Square,
+        ComponentKind::Sawtooth,
+        ComponentKind::Triangle,
+        ComponentKind::Pulse,
+        ComponentKind::Dc,
+        ComponentKind::Chirp,
+        ComponentKind::Modulated,
+        ComponentKind::Noise,
+    ];
+
+    fn name(self) -> &'static str {
+        self.default_component().name()
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ComponentKind::Sine => "Pure tone",
+            ComponentKind::Square => "Odd harmonics, falling off as 1/k",
+            ComponentKind::Sawtooth => "All harmonics, falling off as 1/k",
+            ComponentKind::Triangle => "Odd harmonics, falling off as 1/k²",
+            ComponentKind::Pulse => "Rectangular wave with adjustable duty cycle",
+            ComponentKind::Dc => "Constant offset",
+            ComponentKind::Chirp => "Linear frequency sweep",
+            ComponentKind::Modulated => "Amplitude modulation of one component by another",
+            ComponentKind::Noise => "Uniform white noise",
+        }
+    }
+
+    /// A new component of this kind, with default parameters.
+    fn default_component(self) -> Component {
+        match self {
+            ComponentKind::Sine => Component::Sine {
+                frequency: 100.0,
+                amplitude: 1.0,
+                phase: 0.0,
+            },
+            ComponentKind::Square => Component::Square {
+                frequency: 100.0,
+                amplitude: 1.0,
+                phase: 0.0,
+            },
+            ComponentKind::Sawtooth => Component::Sawtooth {
+                frequency: 100.0,
+                amplitude: 1.0,
+                phase: 0.0,
+            },
+            ComponentKind::Triangle => Component::Triangle {
+                frequency: 100.0,
+                amplitude: 1.0,
+                phase: 0.0,
+            },
+            ComponentKind::Pulse => Component::Pulse {
+                frequency: 100.0,
+                amplitude: 1.0,
+                phase: 0.0,
+                duty: 0.25,
+            },
+            ComponentKind::Dc => Component::Dc { offset: 0.5 },
+            ComponentKind::Chirp => Component::Chirp {
+                f_start: 50.0,
+                f_end: 500.0,
+                amplitude: 1.0,
+                duration: 0.25,
+            },
+            ComponentKind::Modulated => Component::Modulated {
+                carrier: Box::new(Component::Sine {
+                    frequency: 500.0,
+                    amplitude: 1.0,
+                    phase: 0.0,
+                }),
+                modulator: Box::new(Component::Sine {
+                    frequency: 20.0,
+                    amplitude: 1.0,
+                    phase: 0.0,
+                }),
+                depth: 0.5,
+            },
+            ComponentKind::Noise => Component::Noise {
+                amplitude: 1.0,
+                seed: 0,
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 enum Component {
     Sine {
         frequency: f64,
         amplitude: f64,
         phase: f64,
     },
-    Square {
+    Square {
+        frequency: f64,
+        amplitude: f64,
+        phase: f64,
+    },
+    Sawtooth {
         frequency: f64,
         amplitude: f64,
         phase: f64,
     },
-    Sawtooth {
+    Triangle {
+        frequency: f64,
+        amplitude: f64,
+        phase: f64,
+    },
+    /// Rectangular wave that is high for the `duty` fraction of each period.
+    Pulse {
         frequency: f64,
         amplitude: f64,
         phase: f64,
+        duty: f64,
+    },
+    Dc {
+        offset: f64,
+    },
+    Noise {
+        amplitude: f64,
+        seed: u64,
+    },
+    /// Linear frequency sweep from `f_start` to `f_end`, repeated every `duration` seconds.
+    Chirp {
+        f_start: f64,
+        f_end: f64,
+        amplitude: f64,
+        duration: f64,
+    },
+    /// Amplitude modulation: `carrier(t) * (1 + depth * modulator(t))`.
+    Modulated {
+        carrier: Box<Component>,
+        modulator: Box<Component>,
+        depth: f64,
     },
 }
 
 impl Component {
-    pub fn build(&self) -> PeriodicFunction<f64> {
+    /// Builds the function generating this component.
+    ///
+    /// If `band_limit` is set, square and sawtooth waves are made of only their harmonics below
+    /// that frequency, instead of jumping between their extremes, which aliases.
+    pub fn build(&self, band_limit: Option<f64>) -> PeriodicFunction<f64> {
         match self {
             Component::Sine {
                 frequency,
@@ -331,12 +5171,210 @@ impl Component {
                 frequency,
                 amplitude,
                 phase,
-            } => square!(*frequency, *amplitude, *phase),
+            } => match band_limit {
+                Some(limit) => {
+                    functions::band_limited_square(*frequency, *amplitude, *phase, limit)
+                }
+                None => square!(*frequency, *amplitude, *phase),
+            },
+            Component::Sawtooth {
+                frequency,
+                amplitude,
+                phase,
+            } => match band_limit {
+                Some(limit) => {
+                    functions::band_limited_sawtooth(*frequency, *amplitude, *phase, limit)
+                }
+                None => sawtooth!(*frequency, *amplitude, *phase),
+            },
+            Component::Triangle {
+                frequency,
+                amplitude,
+                phase,
+            } => functions::triangle(*frequency, *amplitude, *phase),
+            Component::Pulse {
+                frequency,
+                amplitude,
+                phase,
+                duty,
+            } => functions::pulse(*frequency, *amplitude, *phase, *duty),
+            Component::Dc { offset } => dc_bias!(*offset),
+            Component::Noise { amplitude, seed } => functions::noise(*amplitude, *seed),
+            Component::Chirp {
+                f_start,
+                f_end,
+                amplitude,
+                duration,
+            } => functions::chirp(*f_start, *f_end, *amplitude, *duration),
+            Component::Modulated {
+                carrier,
+                modulator,
+                depth,
+            } => {
+                let (carrier, modulator, depth) = (
+                    carrier.build(band_limit),
+                    modulator.build(band_limit),
+                    *depth,
+                );
+                PeriodicFunction::custom(move |t| {
+                    carrier.sample(t) * (1.0 + depth * modulator.sample(t))
+                })
+            }
+        }
+    }
+
+    /// Rust code building this component with `wavegen`, or `None` if it can't be written
+    /// down briefly.
+    ///
+    /// Components that `wavegen` has macros for use them; the others are written as
+    /// `PeriodicFunction::custom` closures.
+    pub fn to_code(&self) -> Option<String> {
+        Some(match self {
+            Component::Sine {
+                frequency,
+                amplitude,
+                phase,
+            } => format!("sine!({frequency:?}, {amplitude:?}, {phase:?})"),
+            Component::Square {
+                frequency,
+                amplitude,
+                phase,
+            } => format!("square!({frequency:?}, {amplitude:?}, {phase:?})"),
             Component::Sawtooth {
                 frequency,
                 amplitude,
                 phase,
-            } => sawtooth!(*frequency, *amplitude, *phase),
+            } => format!("sawtooth!({frequency:?}, {amplitude:?}, {phase:?})"),
+            Component::Triangle {
+                frequency,
+                amplitude,
+                phase,
+            } => format!(
+                "PeriodicFunction::custom(|t: f64| {amplitude:?} * (1.0 - 4.0 * ((t * {frequency:?} \
+                 + {phase:?} + 0.25).fract() - 0.5).abs()))"
+            ),
+            Component::Pulse {
+                frequency,
+                amplitude,
+                phase,
+                duty,
+            } => format!(
+                "PeriodicFunction::custom(|t: f64| if (t * {frequency:?} + {phase:?}).fract() < \
+                 {duty:?} {{ {amplitude:?} }} else {{ -{amplitude:?} }})"
+            ),
+            Component::Dc { offset } => format!("dc_bias!({offset:?})"),
+            Component::Noise { .. } => return None,
+            Component::Chirp {
+                f_start,
+                f_end,
+                amplitude,
+                duration,
+            } => format!(
+                "PeriodicFunction::custom(|t: f64| {{ let t = t % {duration:?}; {amplitude:?} * \
+                 (std::f64::consts::TAU * ({f_start:?} * t + {:?} * t * t / 2.0)).sin() }})",
+                (f_end - f_start) / duration
+            ),
+            Component::Modulated {
+                carrier,
+                modulator,
+                depth,
+            } => format!(
+                "{{\n    let carrier = {};\n    let modulator = {};\n    \
+                 PeriodicFunction::custom(move |t: f64| carrier.sample(t) * (1.0 + {depth:?} * \
+                 modulator.sample(t)))\n}}",
+                carrier.to_code()?.replace('\n', "\n    "),
+                modulator.to_code()?.replace('\n', "\n    "),
+            ),
+        })
+    }
+
+    /// Whether band limiting changes this component, as it is or contains a square or sawtooth
+    /// wave.
+    pub fn has_harmonics_to_limit(&self) -> bool {
+        match self {
+            Component::Square { .. } | Component::Sawtooth { .. } => true,
+            Component::Modulated {
+                carrier, modulator, ..
+            } => carrier.has_harmonics_to_limit() || modulator.has_harmonics_to_limit(),
+            _ => false,
+        }
+    }
+
+    /// Default instances of the basic component types, e.g. for picking the parts of
+    /// [`Component::Modulated`].
+    pub fn basic_defaults() -> [Component; 4] {
+        [
+            ComponentKind::Sine,
+            ComponentKind::Square,
+            ComponentKind::Sawtooth,
+            ComponentKind::Triangle,
+        ]
+        .map(ComponentKind::default_component)
+    }
+
+    /// Human-readable name of this component's type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Component::Sine { .. } => "Sine",
+            Component::Square { .. } => "Square",
+            Component::Sawtooth { .. } => "Sawtooth",
+            Component::Triangle { .. } => "Triangle",
+            Component::Pulse { .. } => "Pulse",
+            Component::Dc { .. } => "DC",
+            Component::Noise { .. } => "Noise",
+            Component::Chirp { .. } => "Chirp",
+            Component::Modulated { .. } => "AM",
+        }
+    }
+
+    /// The component's parameters, as text.
+    pub fn describe(&self) -> String {
+        match self {
+            Component::Sine {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Square {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Sawtooth {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Triangle {
+                frequency,
+                amplitude,
+                phase,
+            } => format!("f = {frequency} Hz, A = {amplitude}, φ = {phase} turns"),
+            Component::Pulse {
+                frequency,
+                amplitude,
+                phase,
+                duty,
+            } => format!("f = {frequency} Hz, A = {amplitude}, φ = {phase} turns, duty = {duty}"),
+            Component::Dc { offset } => format!("offset = {offset}"),
+            Component::Noise { amplitude, seed } => format!("A = {amplitude}, seed = {seed}"),
+            Component::Chirp {
+                f_start,
+                f_end,
+                amplitude,
+                duration,
+            } => format!("f = {f_start} → {f_end} Hz over {duration} s, A = {amplitude}"),
+            Component::Modulated {
+                carrier,
+                modulator,
+                depth,
+            } => format!(
+                "carrier = {} ({}), modulator = {} ({}), depth = {depth}",
+                carrier.name(),
+                carrier.describe(),
+                modulator.name(),
+                modulator.describe()
+            ),
         }
     }
 
@@ -356,7 +5394,136 @@ impl Component {
                 frequency,
                 amplitude: _,
                 phase: _,
+            }
+            | Component::Triangle {
+                frequency,
+                amplitude: _,
+                phase: _,
+            }
+            | Component::Pulse {
+                frequency,
+                amplitude: _,
+                phase: _,
+                duty: _,
             } => *frequency,
+            Component::Dc { offset: _ }
+            | Component::Noise {
+                amplitude: _,
+                seed: _,
+            } => 0.0,
+            Component::Chirp {
+                f_start,
+                f_end,
+                amplitude: _,
+                duration: _,
+            } => (f_start + f_end) / 2.0,
+            Component::Modulated {
+                carrier,
+                modulator: _,
+                depth: _,
+            } => carrier.frequency(),
+        }
+    }
+
+    /// Lowest and highest frequency of this component.
+    pub fn frequency_range(&self) -> (f64, f64) {
+        match self {
+            Component::Chirp {
+                f_start,
+                f_end,
+                amplitude: _,
+                duration: _,
+            } => (f_start.min(*f_end), f_start.max(*f_end)),
+            // The sidebands:
+            Component::Modulated {
+                carrier,
+                modulator,
+                depth: _,
+            } => {
+                let (carrier_low, carrier_high) = carrier.frequency_range();
+                let (_, modulator_high) = modulator.frequency_range();
+                (
+                    (carrier_low - modulator_high).max(0.0),
+                    carrier_high + modulator_high,
+                )
+            }
+            _ => (self.frequency(), self.frequency()),
+        }
+    }
+
+    /// Multiplies all of the component's frequencies by `factor`.
+    pub fn scale_frequencies(&mut self, factor: f64) {
+        match self {
+            Component::Sine { frequency, .. }
+            | Component::Square { frequency, .. }
+            | Component::Sawtooth { frequency, .. }
+            | Component::Triangle { frequency, .. }
+            | Component::Pulse { frequency, .. } => *frequency *= factor,
+            Component::Dc { .. } | Component::Noise { .. } => {}
+            Component::Chirp { f_start, f_end, .. } => {
+                *f_start *= factor;
+                *f_end *= factor;
+            }
+            Component::Modulated {
+                carrier, modulator, ..
+            } => {
+                carrier.scale_frequencies(factor);
+                modulator.scale_frequencies(factor);
+            }
+        }
+    }
+
+    /// Frequency, amplitude and phase of this component, for those of them it has.
+    pub fn basic_parameters_mut(&mut self) -> [Option<&mut f64>; 3] {
+        match self {
+            Component::Sine {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Square {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Sawtooth {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Triangle {
+                frequency,
+                amplitude,
+                phase,
+            }
+            | Component::Pulse {
+                frequency,
+                amplitude,
+                phase,
+                duty: _,
+            } => [Some(frequency), Some(amplitude), Some(phase)],
+            Component::Noise { amplitude, seed: _ }
+            | Component::Chirp {
+                f_start: _,
+                f_end: _,
+                amplitude,
+                duration: _,
+            } => [None, Some(amplitude), None],
+            Component::Dc { offset: _ }
+            | Component::Modulated {
+                carrier: _,
+                modulator: _,
+                depth: _,
+            } => [None, None, None],
+        }
+    }
+
+    /// Whether this component has a meaningful frequency.
+    pub fn is_periodic(&self) -> bool {
+        match self {
+            Component::Dc { .. } | Component::Noise { .. } => false,
+            Component::Modulated { carrier, .. } => carrier.is_periodic(),
+            _ => true,
         }
     }
 
@@ -364,53 +5531,297 @@ impl Component {
         ui: &mut egui::Ui,
         name: impl Into<String>,
         frequency: &mut f64,
-        amplitude: &mut f64,
-        phase: &mut f64,
+        (amplitude, amplitude_unit): (&mut f64, AmplitudeUnit),
+        (phase, phase_unit): (&mut f64, PhaseUnit),
         cache: &mut Cache<T>,
     ) {
         ui.vertical(|ui| {
             ui.label(egui::RichText::new(name).strong());
-            if ui
-                .add(
+            if committed(
+                Step::FREQUENCY.drag_value(
+                    ui,
                     egui::DragValue::new(frequency)
                         .clamp_range(1e-2..=f64::MAX)
                         .prefix("f: ")
                         .suffix(" Hz"),
+                ),
+                *frequency,
+            )
+            .changed()
+                || amplitude_unit.drag_value(ui, amplitude).changed()
+                || phase_unit.slider(ui, phase).changed()
+            {
+                cache.invalidate();
+            }
+        });
+    }
+
+    fn show_pulse_control<T>(
+        ui: &mut egui::Ui,
+        name: impl Into<String>,
+        (frequency, amplitude, phase): (&mut f64, &mut f64, &mut f64),
+        duty: &mut f64,
+        (phase_unit, amplitude_unit): (PhaseUnit, AmplitudeUnit),
+        cache: &mut Cache<T>,
+    ) {
+        ui.vertical(|ui| {
+            Self::show_control(
+                ui,
+                name,
+                frequency,
+                (amplitude, amplitude_unit),
+                (phase, phase_unit),
+                cache,
+            );
+            let (mut response, change) =
+                Step::DUTY.slider(ui, egui::Slider::new(duty, 0.01..=0.99).prefix("Duty: "));
+            if change != 0.0 {
+                *duty = (*duty + change).clamp(0.01, 0.99);
+                response.mark_changed();
+            }
+            if response.changed() {
+                cache.invalidate();
+            }
+        });
+    }
+
+    fn show_offset_control<T>(
+        ui: &mut egui::Ui,
+        name: impl Into<String>,
+        offset: &mut f64,
+        cache: &mut Cache<T>,
+    ) {
+        ui.vertical(|ui| {
+            ui.label(egui::RichText::new(name).strong());
+            if committed(
+                Step::AMPLITUDE.drag_value(ui, egui::DragValue::new(offset).prefix("Offset: ")),
+                *offset,
+            )
+            .changed()
+            {
+                cache.invalidate();
+            }
+        });
+    }
+
+    fn show_noise_control<T>(
+        ui: &mut egui::Ui,
+        name: impl Into<String>,
+        amplitude: &mut f64,
+        seed: &mut u64,
+        cache: &mut Cache<T>,
+    ) {
+        ui.vertical(|ui| {
+            ui.label(egui::RichText::new(name).strong());
+            if committed(
+                Step::AMPLITUDE.drag_value(
+                    ui,
+                    egui::DragValue::new(amplitude)
+                        .clamp_range(0.0..=f64::MAX)
+                        .prefix("A: "),
+                ),
+                *amplitude,
+            )
+            .changed()
+                || committed(ui.add(egui::DragValue::new(seed).prefix("Seed: ")), *seed).changed()
+            {
+                cache.invalidate();
+            }
+        });
+    }
+
+    fn show_chirp_control<T>(
+        ui: &mut egui::Ui,
+        name: impl Into<String>,
+        (f_start, f_end): (&mut f64, &mut f64),
+        amplitude: &mut f64,
+        duration: &mut f64,
+        cache: &mut Cache<T>,
+    ) {
+        ui.vertical(|ui| {
+            ui.label(egui::RichText::new(name).strong());
+            if committed(
+                Step::FREQUENCY.drag_value(
+                    ui,
+                    egui::DragValue::new(f_start)
+                        .clamp_range(0.0..=f64::MAX)
+                        .prefix("f start: ")
+                        .suffix(" Hz"),
+                ),
+                *f_start,
+            )
+            .changed()
+                | committed(
+                    Step::FREQUENCY.drag_value(
+                        ui,
+                        egui::DragValue::new(f_end)
+                            .clamp_range(0.0..=f64::MAX)
+                            .prefix("f end: ")
+                            .suffix(" Hz"),
+                    ),
+                    *f_end,
                 )
                 .changed()
-                || ui
-                    .add(
+                | committed(
+                    Step::AMPLITUDE.drag_value(
+                        ui,
                         egui::DragValue::new(amplitude)
                             .clamp_range(0.0..=f64::MAX)
                             .prefix("A: "),
-                    )
-                    .changed()
-                || ui
-                    .add(egui::Slider::new(phase, 0.0..=1.0).prefix("φ: "))
-                    .changed()
+                    ),
+                    *amplitude,
+                )
+                .changed()
+                | committed(
+                    Step::DURATION.drag_value(
+                        ui,
+                        egui::DragValue::new(duration)
+                            .clamp_range(1e-3..=f64::MAX)
+                            .prefix("Duration: ")
+                            .suffix(" s"),
+                    ),
+                    *duration,
+                )
+                .changed()
             {
                 cache.invalidate();
             }
         });
     }
 
-    pub fn show<T>(&mut self, ui: &mut egui::Ui, cache: &mut Cache<T>) {
+    /// Lets the user pick the type of a part of a compound component, and edit it.
+    fn show_part<T>(
+        ui: &mut egui::Ui,
+        label: &str,
+        part: &mut Component,
+        (phase_unit, amplitude_unit): (PhaseUnit, AmplitudeUnit),
+        cache: &mut Cache<T>,
+    ) {
+        ui.collapsing(label, |ui| {
+            egui::ComboBox::from_id_source(label)
+                .selected_text(part.name())
+                .show_ui(ui, |ui| {
+                    for default in Component::basic_defaults() {
+                        if ui
+                            .selectable_label(part.name() == default.name(), default.name())
+                            .clicked()
+                            && part.name() != default.name()
+                        {
+                            *part = default;
+                            cache.invalidate();
+                        }
+                    }
+                });
+            part.show(ui, phase_unit, amplitude_unit, cache);
+        });
+    }
+
+    pub fn show<T>(
+        &mut self,
+        ui: &mut egui::Ui,
+        phase_unit: PhaseUnit,
+        amplitude_unit: AmplitudeUnit,
+        cache: &mut Cache<T>,
+    ) {
         match self {
             Component::Sine {
                 frequency,
                 amplitude,
                 phase,
-            } => Self::show_control(ui, "Sine", frequency, amplitude, phase, cache),
+            } => Self::show_control(
+                ui,
+                "Sine",
+                frequency,
+                (amplitude, amplitude_unit),
+                (phase, phase_unit),
+                cache,
+            ),
             Component::Square {
                 frequency,
                 amplitude,
                 phase,
-            } => Self::show_control(ui, "Square", frequency, amplitude, phase, cache),
+            } => Self::show_control(
+                ui,
+                "Square",
+                frequency,
+                (amplitude, amplitude_unit),
+                (phase, phase_unit),
+                cache,
+            ),
             Component::Sawtooth {
                 frequency,
                 amplitude,
                 phase,
-            } => Self::show_control(ui, "Sawtooth", frequency, amplitude, phase, cache),
+            } => Self::show_control(
+                ui,
+                "Sawtooth",
+                frequency,
+                (amplitude, amplitude_unit),
+                (phase, phase_unit),
+                cache,
+            ),
+            Component::Triangle {
+                frequency,
+                amplitude,
+                phase,
+            } => Self::show_control(
+                ui,
+                "Triangle",
+                frequency,
+                (amplitude, amplitude_unit),
+                (phase, phase_unit),
+                cache,
+            ),
+            Component::Pulse {
+                frequency,
+                amplitude,
+                phase,
+                duty,
+            } => Self::show_pulse_control(
+                ui,
+                "Pulse",
+                (frequency, amplitude, phase),
+                duty,
+                (phase_unit, amplitude_unit),
+                cache,
+            ),
+            Component::Dc { offset } => Self::show_offset_control(ui, "DC", offset, cache),
+            Component::Noise { amplitude, seed } => {
+                Self::show_noise_control(ui, "Noise", amplitude, seed, cache);
+            }
+            Component::Chirp {
+                f_start,
+                f_end,
+                amplitude,
+                duration,
+            } => {
+                Self::show_chirp_control(ui, "Chirp", (f_start, f_end), amplitude, duration, cache)
+            }
+            Component::Modulated {
+                carrier,
+                modulator,
+                depth,
+            } => {
+                ui.vertical(|ui| {
+                    ui.label(egui::RichText::new("AM").strong());
+                    if committed(
+                        Step::AMPLITUDE.drag_value(
+                            ui,
+                            egui::DragValue::new(depth)
+                                .clamp_range(0.0..=f64::MAX)
+                                .prefix("Depth: "),
+                        ),
+                        *depth,
+                    )
+                    .changed()
+                    {
+                        cache.invalidate();
+                    }
+                    let units = (phase_unit, amplitude_unit);
+                    Self::show_part(ui, "Carrier", carrier, units, cache);
+                    Self::show_part(ui, "Modulator", modulator, units, cache);
+                });
+            }
         };
     }
 }
@@ -423,9 +5834,10 @@ struct History {
 }
 
 impl History {
-    pub fn new() -> Self {
+    /// Keeps at most `max_len` frames, no older than `max_age` seconds.
+    pub fn new(max_len: usize, max_age: f32) -> Self {
         History {
-            frame_times: egui::util::History::new(0..HISTORY_SIZE, MAX_HISTORY_AGE),
+            frame_times: egui::util::History::new(0..max_len, max_age),
         }
     }
 
@@ -459,3 +5871,186 @@ impl History {
             .show(ui, |plot_ui| plot_ui.line(line));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State persisted by the first version of the app, with `n_samples` as a `u16`.
+    const UNVERSIONED_INITIAL: &str = r#"(
+        sample_rate: 3000.0,
+        n_samples: 1000,
+        components: [
+            (inner: Sine(frequency: 100.0, amplitude: 1.0, phase: 0.0), name: "Sine", enabled: true),
+        ],
+    )"#;
+
+    /// Unversioned state from after `n_samples` became a `usize`, with a few later settings.
+    const UNVERSIONED_LATER: &str = r#"(
+        sample_rate: 48000.0,
+        n_samples: 100000,
+        components: [
+            (
+                inner: Square(frequency: 440.0, amplitude: 0.5, phase: 0.25),
+                name: "Square",
+                enabled: true,
+                muted: true,
+                cents: 5.0,
+            ),
+        ],
+        fmax_scale: 2.0,
+        window: Hann,
+        spectrum_db: true,
+    )"#;
+
+    #[test]
+    fn restores_unversioned_state() {
+        let app = Main::restore(UNVERSIONED_INITIAL).expect("restores");
+        assert_eq!(app.version, STATE_VERSION);
+        assert_eq!(app.n_samples, 1000);
+        assert_eq!(app.components.len(), 1);
+        assert_eq!(app.components[0].name, "Sine");
+        assert_eq!(app.window, WindowFunction::default());
+
+        let app = Main::restore(UNVERSIONED_LATER).expect("restores");
+        assert_eq!(app.version, STATE_VERSION);
+        assert_eq!(app.n_samples, 100_000);
+        assert!(app.components[0].muted);
+        assert_eq!(app.window, WindowFunction::Hann);
+        assert!(app.spectrum_db);
+    }
+
+    #[test]
+    fn round_trips_current_state() {
+        let app = Main {
+            n_samples: 4321,
+            ..Main::default()
+        };
+        let ron = ron::to_string(&app).expect("serializes");
+
+        let restored = Main::restore(&ron).expect("restores");
+        assert_eq!(restored.version, STATE_VERSION);
+        assert_eq!(restored.n_samples, 4321);
+    }
+
+    /// Checks that `component` is written as the code `$code`, and that the code samples the same
+    /// values as `function`.
+    macro_rules! assert_code {
+        ($component:expr, $function:expr, $code:expr) => {{
+            let strip = |code: &str| code.replace(char::is_whitespace, "");
+            assert_eq!(
+                $component.to_code().map(|code| strip(&code)),
+                Some(strip(stringify!($code)))
+            );
+            let code: PeriodicFunction<f64> = $code;
+            let function = $function;
+            for i in 0..1000 {
+                let t = f64::from(i) * 0.0037;
+                assert!(
+                    (code.sample(t) - function.sample(t)).abs() < 1e-12,
+                    "t = {t}"
+                );
+            }
+        }};
+    }
+
+    #[test]
+    fn code_samples_like_the_functions() {
+        assert_code!(
+            Component::Triangle {
+                frequency: 3.0,
+                amplitude: 0.5,
+                phase: 0.1,
+            },
+            functions::triangle(3.0, 0.5, 0.1),
+            PeriodicFunction::custom(
+                |t: f64| 0.5 * (1.0 - 4.0 * ((t * 3.0 + 0.1 + 0.25).fract() - 0.5).abs())
+            )
+        );
+        assert_code!(
+            Component::Pulse {
+                frequency: 3.0,
+                amplitude: 0.5,
+                phase: 0.1,
+                duty: 0.3,
+            },
+            functions::pulse(3.0, 0.5, 0.1, 0.3),
+            PeriodicFunction::custom(|t: f64| if (t * 3.0 + 0.1).fract() < 0.3 {
+                0.5
+            } else {
+                -0.5
+            })
+        );
+        assert_code!(
+            Component::Chirp {
+                f_start: 10.0,
+                f_end: 20.0,
+                amplitude: 0.5,
+                duration: 2.0,
+            },
+            functions::chirp(10.0, 20.0, 0.5, 2.0),
+            PeriodicFunction::custom(|t: f64| {
+                let t = t % 2.0;
+                0.5 * (std::f64::consts::TAU * (10.0 * t + 5.0 * t * t / 2.0)).sin()
+            })
+        );
+    }
+
+    #[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+    #[test]
+    fn midi_notes_add_and_mute_components() {
+        fn sine(c: &ComponentWrapper) -> (f64, f64) {
+            match c.inner {
+                Component::Sine {
+                    frequency,
+                    amplitude,
+                    ..
+                } => (frequency, amplitude),
+                _ => panic!("not a sine"),
+            }
+        }
+
+        let mut components = vec![];
+        let on = |velocity| midi::Event::NoteOn { note: 69, velocity };
+        let off = midi::Event::NoteOff { note: 69 };
+        let reference_a = note::DEFAULT_REFERENCE_A;
+
+        // Releasing a note that was never pressed changes nothing.
+        assert!(!play_midi_event(&mut components, off, reference_a));
+        assert!(components.is_empty());
+
+        assert!(play_midi_event(&mut components, on(127), reference_a));
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].name, "MIDI A4");
+        assert!(!components[0].muted);
+        assert_eq!(sine(&components[0]), (440.0, 1.0));
+
+        assert!(play_midi_event(&mut components, off, reference_a));
+        assert!(components[0].muted);
+        assert!(!play_midi_event(&mut components, off, reference_a));
+
+        // Pressing the note again unmutes the same component at the new velocity.
+        assert!(play_midi_event(&mut components, on(64), reference_a));
+        assert_eq!(components.len(), 1);
+        assert!(!components[0].muted);
+        assert_eq!(sine(&components[0]), (440.0, 64.0 / 127.0));
+    }
+
+    #[test]
+    fn formats_bytes_in_the_largest_fitting_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(1024 * 1024), "1.0 MiB");
+        assert_eq!(format_bytes(3 << 30), "3.0 GiB");
+    }
+
+    #[test]
+    fn rejects_newer_or_broken_state() {
+        let newer = format!("(version: {}, n_samples: 10)", STATE_VERSION + 1);
+        assert!(Main::restore(&newer).is_none());
+        assert!(Main::restore("(n_samples: -1)").is_none());
+        assert!(Main::restore("not ron").is_none());
+    }
+}
@@ -0,0 +1,103 @@
+//! Playing the generated waveform on the default audio output device.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use std::sync::mpsc;
+
+/// A waveform playing in a loop. Playback stops when this is dropped.
+pub struct Playback {
+    _stream: cpal::Stream,
+    errors: mpsc::Receiver<cpal::StreamError>,
+}
+
+impl Playback {
+    /// Starts looping the samples of `channels`, resampling them from `sample_rate` to the
+    /// device's rate.
+    ///
+    /// The channels are played on the device's channels in order. The last one is repeated on
+    /// any further device channels. `ctx` is repainted when the stream fails, so the error is
+    /// picked up by [`Playback::error`].
+    pub fn start(
+        channels: Vec<Vec<f64>>,
+        sample_rate: f64,
+        ctx: egui::Context,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if channels.is_empty() || channels.iter().any(Vec::is_empty) {
+            return Err("there is nothing to play".into());
+        }
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no audio output device available")?;
+        let config = device.default_output_config()?;
+        let (sender, errors) = mpsc::channel();
+        let on_error = move |e| {
+            // The receiver only goes away with the stream.
+            let _ = sender.send(e);
+            ctx.request_repaint();
+        };
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => {
+                build::<f32>(&device, &config.into(), channels, sample_rate, on_error)?
+            }
+            cpal::SampleFormat::I16 => {
+                build::<i16>(&device, &config.into(), channels, sample_rate, on_error)?
+            }
+            cpal::SampleFormat::U16 => {
+                build::<u16>(&device, &config.into(), channels, sample_rate, on_error)?
+            }
+            other => return Err(format!("unsupported sample format: {other}").into()),
+        };
+        stream.play()?;
+
+        Ok(Self {
+            _stream: stream,
+            errors,
+        })
+    }
+
+    /// The error the stream failed with since the last call, if any.
+    pub fn error(&self) -> Option<cpal::StreamError> {
+        self.errors.try_recv().ok()
+    }
+}
+
+fn build<T: SizedSample + FromSample<f32>>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sources: Vec<Vec<f64>>,
+    sample_rate: f64,
+    on_error: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    let channels = usize::from(config.channels);
+    let step = sample_rate / f64::from(config.sample_rate.0);
+    #[allow(clippy::cast_precision_loss)]
+    let len = sources.iter().map(Vec::len).min().unwrap_or(0) as f64;
+    let mut position = 0.0;
+
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            for frame in data.chunks_mut(channels) {
+                for (i, sample) in frame.iter_mut().enumerate() {
+                    let source = &sources[i.min(sources.len() - 1)];
+                    #[allow(clippy::cast_possible_truncation)]
+                    let value = interpolate(source, position).clamp(-1.0, 1.0) as f32;
+                    *sample = T::from_sample(value);
+                }
+                position = (position + step) % len;
+            }
+        },
+        on_error,
+        None,
+    )
+}
+
+/// Linearly interpolates `samples` at fractional index `position`, wrapping around at the end.
+fn interpolate(samples: &[f64], position: f64) -> f64 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let i = position as usize % samples.len();
+    let next = samples[(i + 1) % samples.len()];
+    let t = position.fract();
+
+    samples[i] * (1.0 - t) + next * t
+}
@@ -0,0 +1,102 @@
+//! Cross-correlation and convolution of two signals, computed through the FFT.
+
+use crate::fft;
+use rustfft::num_complex::Complex;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Operation {
+    /// `r[k] = Σ a[n + k] b[n]`: how much `a` looks like `b` shifted by `k` samples.
+    #[default]
+    Correlation,
+    /// `c[k] = Σ a[n] b[k - n]`: `a` filtered by `b`, or the other way around.
+    Convolution,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 2] = [Operation::Correlation, Operation::Convolution];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Correlation => "Cross-correlation",
+            Operation::Convolution => "Convolution",
+        }
+    }
+}
+
+/// Linear (not circular) correlation or convolution of `a` and `b`.
+///
+/// Returns `[k, value]` for every `k` where the signals overlap: lags from `1 - b.len()` to
+/// `a.len() - 1` for the correlation, and from 0 to `a.len() + b.len() - 2` for the
+/// convolution.
+pub fn compute(a: &[f64], b: &[f64], operation: Operation) -> Vec<[f64; 2]> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let out_len = a.len() + b.len() - 1;
+    // Padding to the full output length keeps the ends from wrapping around onto each other.
+    let len = out_len.next_power_of_two();
+    let transform = |samples: &[f64]| {
+        let mut buffer: Vec<_> = samples.iter().map(|s| Complex::new(*s, 0.0)).collect();
+        buffer.resize(len, Complex::default());
+        fft::forward(len).process(&mut buffer);
+        buffer
+    };
+    let mut product = transform(a);
+    for (x, y) in product.iter_mut().zip(transform(b)) {
+        *x *= match operation {
+            Operation::Correlation => y.conj(),
+            Operation::Convolution => y,
+        };
+    }
+    fft::inverse(len).process(&mut product);
+
+    #[allow(clippy::cast_precision_loss)]
+    let scale = 1.0 / len as f64;
+    #[allow(clippy::cast_precision_loss)]
+    match operation {
+        // Negative lags wrapped around to the end.
+        Operation::Correlation => (1 - b.len() as isize..a.len() as isize)
+            .map(|k| {
+                [
+                    k as f64,
+                    product[k.rem_euclid(len as isize) as usize].re * scale,
+                ]
+            })
+            .collect(),
+        Operation::Convolution => product[..out_len]
+            .iter()
+            .enumerate()
+            .map(|(k, x)| [k as f64, x.re * scale])
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_direct_computation() {
+        let a = [1.0, 2.0, 3.0, -1.0];
+        let b = [0.5, -2.0, 1.0];
+
+        let convolution = compute(&a, &b, Operation::Convolution);
+        let expected = [0.5, -1.0, -1.5, -4.5, 5.0, -1.0];
+        assert_eq!(convolution.len(), expected.len());
+        for (k, ([lag, value], expected)) in convolution.iter().zip(expected).enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let k = k as f64;
+            assert!((lag - k).abs() < f64::EPSILON);
+            assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+        }
+
+        let correlation = compute(&a, &b, Operation::Correlation);
+        // Lags -2 to 3.
+        let expected = [1.0, 0.0, -0.5, -6.0, 3.5, -0.5];
+        assert_eq!(correlation.len(), expected.len());
+        assert_eq!(correlation.first().map(|p| p[0]), Some(-2.0));
+        for ([_, value], expected) in correlation.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+        }
+    }
+}
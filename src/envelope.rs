@@ -0,0 +1,130 @@
+//! Amplitude envelopes, shaping a component over the generated window.
+
+/// An attack-decay-sustain-release envelope, stretched over the whole sample window.
+///
+/// The level rises from 0 to 1 during `attack`, falls to `sustain` during `decay`, and holds
+/// there until the last `release` seconds of the window, over which it falls back to 0.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Envelope {
+    /// In seconds.
+    pub attack: f64,
+    /// In seconds.
+    pub decay: f64,
+    /// Level held between the decay and the release, from 0 to 1.
+    pub sustain: f64,
+    /// In seconds.
+    pub release: f64,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self {
+            attack: 0.01,
+            decay: 0.05,
+            sustain: 0.7,
+            release: 0.05,
+        }
+    }
+}
+
+impl Envelope {
+    /// Level of the envelope at time `t`, in a window `duration` seconds long.
+    ///
+    /// If the window is too short for all stages, the release starts from wherever the attack
+    /// or decay got to.
+    pub fn value(&self, t: f64, duration: f64) -> f64 {
+        let release_start = (duration - self.release).max(0.0);
+        if t < release_start {
+            self.attack_decay(t)
+        } else if self.release > 0.0 {
+            self.attack_decay(release_start) * ((duration - t) / self.release).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Level of the envelope at time `t`, before the release.
+    fn attack_decay(&self, t: f64) -> f64 {
+        if t < self.attack {
+            t / self.attack
+        } else if t < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (t - self.attack) / self.decay
+        } else {
+            self.sustain
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{actual} isn't {expected}"
+        );
+    }
+
+    #[test]
+    fn follows_the_stages() {
+        let envelope = Envelope::default();
+        for (t, expected) in [
+            (0.0, 0.0),
+            (0.005, 0.5),
+            // End of the attack, start of the decay.
+            (0.01, 1.0),
+            (0.035, 0.85),
+            // End of the decay.
+            (0.06, 0.7),
+            (0.5, 0.7),
+            // Start of the release.
+            (0.95, 0.7),
+            (0.975, 0.35),
+            (1.0, 0.0),
+        ] {
+            assert_close(envelope.value(t, 1.0), expected);
+        }
+    }
+
+    #[test]
+    fn skips_zero_length_stages() {
+        let envelope = Envelope {
+            attack: 0.0,
+            decay: 0.0,
+            sustain: 0.5,
+            release: 0.0,
+        };
+        assert_close(envelope.value(0.0, 1.0), 0.5);
+        assert_close(envelope.value(0.999, 1.0), 0.5);
+        assert_close(envelope.value(1.0, 1.0), 0.0);
+
+        // Without a decay, the attack ends at the sustain level.
+        let envelope = Envelope {
+            decay: 0.0,
+            ..Envelope::default()
+        };
+        assert_close(envelope.value(0.005, 1.0), 0.5);
+        assert_close(envelope.value(0.01, 1.0), 0.7);
+
+        // Without an attack, the decay starts at full level.
+        let envelope = Envelope {
+            attack: 0.0,
+            ..Envelope::default()
+        };
+        assert_close(envelope.value(0.0, 1.0), 1.0);
+        assert_close(envelope.value(0.025, 1.0), 0.85);
+    }
+
+    #[test]
+    fn releases_from_where_a_short_window_got_to() {
+        let envelope = Envelope {
+            release: 0.02,
+            ..Envelope::default()
+        };
+        // The release starts at 0.03 s, in the middle of the decay.
+        assert_close(envelope.value(0.03, 0.05), 0.88);
+        assert_close(envelope.value(0.04, 0.05), 0.44);
+        assert_close(envelope.value(0.05, 0.05), 0.0);
+    }
+}
@@ -0,0 +1,323 @@
+//! Writing the generated signal to files, and reading recorded signals back in.
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::plot_image::{self, PlotLabels};
+use crate::util::PlotData;
+use std::fmt::Write as _;
+
+/// Data that can be exported from the app.
+pub enum Export {
+    #[cfg(not(target_arch = "wasm32"))]
+    Wav,
+    WaveformCsv,
+    SpectrumCsv,
+    #[cfg(not(target_arch = "wasm32"))]
+    WaveformPng(PlotLabels),
+    #[cfg(not(target_arch = "wasm32"))]
+    SpectrumPng(PlotLabels),
+}
+
+impl Export {
+    /// Whether the export is of the spectrum, which is computed in the background.
+    pub fn needs_spectrum(&self) -> bool {
+        match self {
+            Export::SpectrumCsv => true,
+            #[cfg(not(target_arch = "wasm32"))]
+            Export::SpectrumPng(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Asks the user where to save the data, and saves it there.
+    ///
+    /// On the web, the file is offered as a browser download instead.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn run(self, plot_data: &PlotData, sample_rate: f64) -> Result<(), Error> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Export::Wav => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("WAV", &["wav"])
+                    .set_file_name("waveform.wav")
+                    .save_file()
+                {
+                    let channel = |points: &[[f64; 2]]| points.iter().map(|[_, y]| *y).collect();
+                    let mut channels: Vec<Vec<f64>> = vec![channel(&plot_data.waveform)];
+                    if let Some(right) = &plot_data.right {
+                        channels.push(channel(&right.waveform));
+                    }
+                    write_wav(&path, sample_rate, &channels)?;
+                }
+                Ok(())
+            }
+            Export::WaveformCsv => {
+                let csv = match &plot_data.right {
+                    Some(right) => stereo_csv(&plot_data.waveform, &right.waveform),
+                    None => csv("time,value", &plot_data.waveform),
+                };
+                save_text("waveform.csv", "text/csv", &csv)
+            }
+            Export::SpectrumCsv => save_text(
+                "spectrum.csv",
+                "text/csv",
+                &csv("frequency,magnitude", &plot_data.spectrum.points),
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            Export::WaveformPng(labels) => save_png("waveform.png", &labels, &plot_data.waveform),
+            #[cfg(not(target_arch = "wasm32"))]
+            Export::SpectrumPng(labels) => {
+                save_png("spectrum.png", &labels, &plot_data.spectrum.points)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The file was written by a newer version of the app.
+    #[cfg(not(target_arch = "wasm32"))]
+    UnsupportedVersion(u32),
+    /// The sample rate doesn't round to one a WAV file can hold.
+    #[cfg(not(target_arch = "wasm32"))]
+    UnsupportedSampleRate(f64),
+    #[cfg(not(target_arch = "wasm32"))]
+    Wav(hound::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    Image(image::ImageError),
+    #[cfg(target_arch = "wasm32")]
+    Js(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::Json(e) => e.fmt(f),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::UnsupportedVersion(v) => write!(f, "unsupported file version: {v}"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::UnsupportedSampleRate(r) => write!(f, "unsupported sample rate: {r} Hz"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Wav(e) => e.fmt(f),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Image(e) => e.fmt(f),
+            #[cfg(target_arch = "wasm32")]
+            Error::Js(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<hound::Error> for Error {
+    fn from(e: hound::Error) -> Self {
+        Error::Wav(e)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Self {
+        Error::Image(e)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<wasm_bindgen::JsValue> for Error {
+    fn from(e: wasm_bindgen::JsValue) -> Self {
+        Error::Js(format!("{e:?}"))
+    }
+}
+
+/// Formats `rows` as CSV, preceded by the `header` line.
+pub fn csv(header: &str, rows: &[[f64; 2]]) -> String {
+    let mut out = format!("{header}\n");
+    for [x, y] in rows {
+        writeln!(out, "{x},{y}").expect("writing to a String cannot fail");
+    }
+    out
+}
+
+/// Formats the `left` and `right` channels of a stereo waveform as CSV, with a column each.
+fn stereo_csv(left: &[[f64; 2]], right: &[[f64; 2]]) -> String {
+    let mut out = String::from("time,left,right\n");
+    for ([t, l], [_, r]) in left.iter().zip(right) {
+        writeln!(out, "{t},{l},{r}").expect("writing to a String cannot fail");
+    }
+    out
+}
+
+/// Writes the samples of each of `channels` to `path` as a 32-bit float WAV file, with as
+/// many channels.
+///
+/// If any sample exceeds ±1.0, the whole signal is scaled down to fit, so that it doesn't clip.
+/// The sample rate is rounded to whole hertz, and must not round to 0.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_wav(
+    path: &std::path::Path,
+    sample_rate: f64,
+    channels: &[Vec<f64>],
+) -> Result<(), Error> {
+    let rounded = sample_rate.round();
+    if !(1.0..=f64::from(u32::MAX)).contains(&rounded) {
+        return Err(Error::UnsupportedSampleRate(sample_rate));
+    }
+    let peak = channels
+        .iter()
+        .flatten()
+        .fold(0.0_f64, |peak, s| peak.max(s.abs()));
+    let scale = if peak > 1.0 { 1.0 / peak } else { 1.0 };
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let spec = hound::WavSpec {
+        channels: u16::try_from(channels.len()).expect("too many channels"),
+        sample_rate: rounded as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    let len = channels.iter().map(Vec::len).min().unwrap_or(0);
+    for i in 0..len {
+        for channel in channels {
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_sample((channel[i] * scale) as f32)?;
+        }
+    }
+
+    Ok(writer.finalize()?)
+}
+
+/// Asks the user where to save the plot of `points`, and saves it there as a PNG image.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_png(file_name: &str, labels: &PlotLabels, points: &[[f64; 2]]) -> Result<(), Error> {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("PNG", &["png"])
+        .set_file_name(file_name)
+        .save_file()
+    {
+        plot_image::render(labels, points).save_with_format(path, image::ImageFormat::Png)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a WAV file, mixing all channels down to mono.
+///
+/// Returns the samples, scaled to `[-1, 1]`, and the sample rate.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_wav(path: &std::path::Path) -> Result<(Vec<f64>, f64), hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let interleaved: Vec<f64> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.map(f64::from))
+            .collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let full_scale = f64::from(1_u32 << (spec.bits_per_sample - 1));
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| f64::from(s) / full_scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+    let channels = spec.channels.max(1);
+    let samples = interleaved
+        .chunks(usize::from(channels))
+        .map(|frame| frame.iter().sum::<f64>() / f64::from(channels))
+        .collect();
+
+    Ok((samples, f64::from(spec.sample_rate)))
+}
+
+/// Lets the user pick a WAV file and reads it with [`read_wav`]. Returns `None` if the dialog
+/// is cancelled.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_wav() -> Result<Option<(Vec<f64>, f64)>, Error> {
+    match rfd::FileDialog::new()
+        .add_filter("WAV", &["wav"])
+        .pick_file()
+    {
+        Some(path) => Ok(Some(read_wav(&path)?)),
+        None => Ok(None),
+    }
+}
+
+/// Lets the user pick a file to save `contents` to. Does nothing if the dialog is cancelled.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_text(file_name: &str, _mime_type: &str, contents: &str) -> Result<(), Error> {
+    let extension = file_name.rsplit('.').next().unwrap_or_default();
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter(&extension.to_uppercase(), &[extension])
+        .set_file_name(file_name)
+        .save_file()
+    {
+        std::fs::write(path, contents)?;
+    }
+
+    Ok(())
+}
+
+/// Lets the user pick a file and reads it. Returns `None` if the dialog is cancelled.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_text(extension: &str) -> Result<Option<String>, Error> {
+    match rfd::FileDialog::new()
+        .add_filter(&extension.to_uppercase(), &[extension])
+        .pick_file()
+    {
+        Some(path) => Ok(Some(std::fs::read_to_string(path)?)),
+        None => Ok(None),
+    }
+}
+
+/// Offers `contents` as a browser download named `file_name`.
+#[cfg(target_arch = "wasm32")]
+pub fn save_text(file_name: &str, mime_type: &str, contents: &str) -> Result<(), Error> {
+    use wasm_bindgen::JsCast;
+
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| Error::Js("no document available".to_owned()))?;
+    let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(contents));
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(
+        &parts,
+        web_sys::BlobPropertyBag::new().type_(mime_type),
+    )?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    Ok(web_sys::Url::revoke_object_url(&url)?)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_sample_rates_a_wav_cannot_hold() {
+        let path = std::env::temp_dir().join("egui-waves-unwritten.wav");
+        for sample_rate in [0.0, 0.4, -1.0, 1e10, f64::NAN] {
+            assert!(matches!(
+                write_wav(&path, sample_rate, &[vec![0.0]]),
+                Err(Error::UnsupportedSampleRate(_))
+            ));
+        }
+        assert!(!path.exists());
+    }
+}
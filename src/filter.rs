@@ -0,0 +1,134 @@
+//! Filtering of the summed waveform, to measure what a filter does to a signal.
+
+use rustfft::num_complex::Complex;
+use std::f64::consts::TAU;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FilterKind {
+    #[default]
+    Lowpass,
+    Highpass,
+    /// Passes a band around the cutoff, with a peak gain of 1. Higher Q narrows the band.
+    Bandpass,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 3] = [
+        FilterKind::Lowpass,
+        FilterKind::Highpass,
+        FilterKind::Bandpass,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FilterKind::Lowpass => "Lowpass",
+            FilterKind::Highpass => "Highpass",
+            FilterKind::Bandpass => "Bandpass",
+        }
+    }
+}
+
+/// A second order (biquad) filter, as in the Audio EQ Cookbook by Robert Bristow-Johnson.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Filter {
+    pub kind: FilterKind,
+    /// Cutoff frequency of the low- and highpass, or center of the bandpass, in Hz.
+    pub cutoff: f64,
+    /// Quality factor. 1/√2 gives the flattest passband.
+    pub q: f64,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            kind: FilterKind::default(),
+            cutoff: 1000.0,
+            q: std::f64::consts::FRAC_1_SQRT_2,
+        }
+    }
+}
+
+/// Coefficients of the transfer function `(b0 + b1 z⁻¹ + b2 z⁻²) / (1 + a1 z⁻¹ + a2 z⁻²)`.
+struct Coefficients {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Filter {
+    fn coefficients(&self, sample_rate: f64) -> Coefficients {
+        let w0 = TAU * self.cutoff / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * self.q);
+        let b = match self.kind {
+            FilterKind::Lowpass => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            FilterKind::Highpass => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            FilterKind::Bandpass => [alpha, 0.0, -alpha],
+        };
+        let a0 = 1.0 + alpha;
+
+        Coefficients {
+            b: b.map(|b| b / a0),
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        }
+    }
+
+    /// Filters `samples`, taken at `sample_rate`, in place. The filter starts at rest.
+    pub fn apply(&self, samples: &mut [f64], sample_rate: f64) {
+        let Coefficients { b, a } = self.coefficients(sample_rate);
+        // Transposed direct form II.
+        let (mut s1, mut s2) = (0.0, 0.0);
+        for x in samples {
+            let y = b[0] * *x + s1;
+            s1 = b[1] * *x - a[0] * y + s2;
+            s2 = b[2] * *x - a[1] * y;
+            *x = y;
+        }
+    }
+
+    /// Gain of the filter at `frequency`, when running at `sample_rate`.
+    pub fn magnitude(&self, frequency: f64, sample_rate: f64) -> f64 {
+        let Coefficients { b, a } = self.coefficients(sample_rate);
+        // z⁻¹ on the unit circle.
+        let z = Complex::from_polar(1.0, -TAU * frequency / sample_rate);
+        let numerator = b[0] + z * (b[1] + z * b[2]);
+        let denominator = 1.0 + z * (a[0] + z * a[1]);
+
+        (numerator / denominator).norm()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flattest_lowpass_is_3_db_down_at_cutoff() {
+        let filter = Filter::default();
+        let sample_rate = 48000.0;
+
+        assert!((filter.magnitude(0.0, sample_rate) - 1.0).abs() < 1e-9);
+        let at_cutoff = 20.0 * filter.magnitude(filter.cutoff, sample_rate).log10();
+        assert!((at_cutoff + 3.01).abs() < 0.01, "{at_cutoff} dB");
+        assert!(filter.magnitude(sample_rate / 2.0, sample_rate) < 1e-6);
+    }
+
+    #[test]
+    fn filtered_sine_matches_magnitude() {
+        let filter = Filter {
+            kind: FilterKind::Bandpass,
+            cutoff: 500.0,
+            q: 2.0,
+        };
+        let sample_rate = 8000.0;
+        let frequency = 700.0;
+        let mut samples: Vec<_> = (0..8000)
+            .map(|i| (TAU * frequency * f64::from(i) / sample_rate).sin())
+            .collect();
+        filter.apply(&mut samples, sample_rate);
+
+        // Past the transient, the sine is scaled by the gain at its frequency.
+        let peak = samples[4000..].iter().fold(0.0_f64, |p, s| p.max(s.abs()));
+        let expected = filter.magnitude(frequency, sample_rate);
+        assert!((peak - expected).abs() < 1e-3, "{peak} != {expected}");
+    }
+}
@@ -0,0 +1,196 @@
+//! Periodic functions not provided by `wavegen` out of the box.
+
+use std::f64::consts::{PI, TAU};
+use wavegen::PeriodicFunction;
+
+/// Most harmonics the band-limited waves are made of, as each sample costs a term per harmonic.
+/// Waves too low for all their harmonics up to the limit are rounded off a little more.
+pub const MAX_HARMONICS: u32 = 512;
+
+/// Symmetric triangle wave, starting at zero and rising, like `wavegen::sine!`.
+///
+/// `phase` is expressed as a fraction of the period, same as in the `wavegen` macros.
+pub fn triangle(frequency: f64, amplitude: f64, phase: f64) -> PeriodicFunction<f64> {
+    PeriodicFunction::custom(move |t: f64| {
+        amplitude * (1.0 - 4.0 * ((t * frequency + phase + 0.25).fract() - 0.5).abs())
+    })
+}
+
+/// Rectangular pulse train, at `amplitude` for the `duty` fraction of each period and at
+/// `-amplitude` for the rest.
+///
+/// `phase` is expressed as a fraction of the period, same as in the `wavegen` macros.
+pub fn pulse(frequency: f64, amplitude: f64, phase: f64, duty: f64) -> PeriodicFunction<f64> {
+    PeriodicFunction::custom(move |t: f64| {
+        if (t * frequency + phase).fract() < duty {
+            amplitude
+        } else {
+            -amplitude
+        }
+    })
+}
+
+/// Square wave like `wavegen::square!`, but made of only its harmonics below `max_frequency`,
+/// so that it doesn't alias when sampled at twice that rate. At most [`MAX_HARMONICS`] are used.
+///
+/// `phase` is a time offset in seconds, same as in `wavegen::square!`.
+pub fn band_limited_square(
+    frequency: f64,
+    amplitude: f64,
+    phase: f64,
+    max_frequency: f64,
+) -> PeriodicFunction<f64> {
+    let harmonics = harmonics_below(frequency, max_frequency);
+    PeriodicFunction::custom(move |t: f64| {
+        let x = TAU * frequency * (t - phase);
+        4.0 * amplitude / PI * harmonic_sum(x, harmonics, 2)
+    })
+}
+
+/// Sawtooth wave like `wavegen::sawtooth!`, but made of only its harmonics below
+/// `max_frequency`, so that it doesn't alias when sampled at twice that rate. At most
+/// [`MAX_HARMONICS`] are used.
+///
+/// `phase` is expressed as a fraction of the period, same as in `wavegen::sawtooth!`.
+pub fn band_limited_sawtooth(
+    frequency: f64,
+    amplitude: f64,
+    phase: f64,
+    max_frequency: f64,
+) -> PeriodicFunction<f64> {
+    let harmonics = harmonics_below(frequency, max_frequency);
+    PeriodicFunction::custom(move |t: f64| {
+        let x = TAU * (frequency * t + phase);
+        -2.0 * amplitude / PI * harmonic_sum(x, harmonics, 1)
+    })
+}
+
+/// Number of harmonics of `frequency` that are below `max_frequency`, up to [`MAX_HARMONICS`].
+fn harmonics_below(frequency: f64, max_frequency: f64) -> u32 {
+    if frequency <= 0.0 {
+        return 0;
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let harmonics = (max_frequency / frequency).ceil().max(1.0) as u32 - 1;
+    harmonics.min(MAX_HARMONICS)
+}
+
+/// `Σ sin(k x) / k` over `k = 1, 1 + step, 1 + 2 step, …` up to `harmonics`.
+///
+/// Only two sines are evaluated: the others follow from
+/// `sin((k + step) x) = 2 cos(step x) sin(k x) - sin((k - step) x)`.
+fn harmonic_sum(x: f64, harmonics: u32, step: u32) -> f64 {
+    let factor = 2.0 * (f64::from(step) * x).cos();
+    let (mut sin, mut previous) = (x.sin(), (x * (1.0 - f64::from(step))).sin());
+    let mut sum = 0.0;
+    for k in (1..=harmonics).step_by(step as usize) {
+        sum += sin / f64::from(k);
+        (sin, previous) = (factor * sin - previous, sin);
+    }
+    sum
+}
+
+/// Linear chirp, sweeping from `f_start` to `f_end` over `duration` seconds, then starting over.
+pub fn chirp(f_start: f64, f_end: f64, amplitude: f64, duration: f64) -> PeriodicFunction<f64> {
+    let rate = (f_end - f_start) / duration;
+    PeriodicFunction::custom(move |t: f64| {
+        let t = t % duration;
+        amplitude * (TAU * (f_start * t + rate * t * t / 2.0)).sin()
+    })
+}
+
+/// Uniform white noise in `[-amplitude, amplitude]`.
+///
+/// Each sample is derived from the sample time and `seed` only, so the same
+/// settings always produce the same waveform.
+pub fn noise(amplitude: f64, seed: u64) -> PeriodicFunction<f64> {
+    PeriodicFunction::custom(move |t: f64| {
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (splitmix64(t.to_bits() ^ seed) >> 11) as f64 / (1_u64 << 53) as f64;
+
+        amplitude * (2.0 * unit - 1.0)
+    })
+}
+
+/// Small, fast, stateless 64-bit mixing function (see <https://prng.di.unimi.it/splitmix64.c>).
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectrum::{self, Settings};
+
+    const SAMPLE_RATE: f64 = 1000.0;
+    /// Fits a whole number of periods into the samples, and its harmonics above the Nyquist
+    /// frequency alias to frequencies that aren't harmonics.
+    const FREQUENCY: f64 = 30.0;
+
+    /// Largest magnitude in the spectrum of `f` outside of the harmonics of [`FREQUENCY`].
+    fn largest_non_harmonic(f: &PeriodicFunction<f64>) -> f64 {
+        let samples: Vec<_> = (0..1000)
+            .map(|i| f.sample(f64::from(i) / SAMPLE_RATE))
+            .collect();
+        let settings = Settings {
+            // Keeps the harmonics on bins.
+            fft_size: Some(samples.len()),
+            ..spectrum::test_settings(SAMPLE_RATE)
+        };
+
+        spectrum::compute(&samples, &settings)
+            .points
+            .iter()
+            .filter(|[f, _]| (f / FREQUENCY).fract().abs() > 1e-9)
+            .map(|[_, m]| *m)
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn band_limited_waves_do_not_alias() {
+        let nyquist = SAMPLE_RATE / 2.0;
+        for (naive, band_limited) in [
+            (
+                wavegen::square!(FREQUENCY, 1.0, 0.0),
+                band_limited_square(FREQUENCY, 1.0, 0.0, nyquist),
+            ),
+            (
+                wavegen::sawtooth!(FREQUENCY, 1.0, 0.0),
+                band_limited_sawtooth(FREQUENCY, 1.0, 0.0, nyquist),
+            ),
+        ] {
+            assert!(largest_non_harmonic(&naive) > 1e-3);
+            assert!(largest_non_harmonic(&band_limited) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn harmonic_sum_matches_direct_sum() {
+        for step in [1, 2] {
+            for x in [0.1, 1.0, 2.5, -4.0] {
+                let direct: f64 = (1..=MAX_HARMONICS)
+                    .step_by(step as usize)
+                    .map(|k| (f64::from(k) * x).sin() / f64::from(k))
+                    .sum();
+                let sum = harmonic_sum(x, MAX_HARMONICS, step);
+                assert!((sum - direct).abs() < 1e-9, "{sum} != {direct}");
+            }
+        }
+    }
+
+    #[test]
+    fn band_limited_waves_match_naive_shape() {
+        // With plenty of harmonics, the waves converge to the naive ones away from the jumps.
+        let square = band_limited_square(1.0, 1.0, 0.0, 10_000.0);
+        let sawtooth = band_limited_sawtooth(1.0, 1.0, 0.0, 10_000.0);
+        for t in [0.1, 0.3, 0.6, 0.9] {
+            assert!((square.sample(t) - wavegen::square!(1.0, 1.0, 0.0).sample(t)).abs() < 1e-2);
+            assert!(
+                (sawtooth.sample(t) - wavegen::sawtooth!(1.0, 1.0, 0.0).sample(t)).abs() < 1e-2
+            );
+        }
+    }
+}
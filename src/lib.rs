@@ -1,5 +1,24 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+mod audio;
+mod correlation;
+mod envelope;
+mod export;
+mod fft;
+mod filter;
+mod functions;
+#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+mod midi;
+mod note;
+#[cfg(not(target_arch = "wasm32"))]
+mod plot_image;
+mod saturation;
+mod sketch;
+mod spectrogram;
+mod spectrum;
+mod undo;
 mod util;
+mod window;
 pub use app::Main;
@@ -0,0 +1,142 @@
+//! Notes played on a MIDI input device, like a keyboard.
+
+use std::sync::mpsc;
+
+/// Name the app is listed under by the MIDI system.
+const CLIENT_NAME: &str = "egui_waves";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// `velocity` is from 1 to 127.
+    NoteOn {
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        note: u8,
+    },
+}
+
+impl Event {
+    /// Parses a MIDI message, on any channel. Other messages than notes give `None`.
+    fn parse(message: &[u8]) -> Option<Self> {
+        let (&status, data) = message.split_first()?;
+        let (&note, data) = data.split_first()?;
+        let velocity = data.first().copied().unwrap_or(0);
+        match status & 0xf0 {
+            // A note on with velocity 0 is the usual shorthand for a note off.
+            0x90 if velocity > 0 => Some(Event::NoteOn { note, velocity }),
+            0x80 | 0x90 => Some(Event::NoteOff { note }),
+            _ => None,
+        }
+    }
+}
+
+/// Names of the available MIDI input ports.
+pub fn ports() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let input = midir::MidiInput::new(CLIENT_NAME)?;
+    let names = input
+        .ports()
+        .iter()
+        .filter_map(|port| input.port_name(port).ok())
+        .collect();
+
+    Ok(names)
+}
+
+/// A connection to a MIDI input port. The port is closed when this is dropped.
+pub struct Input {
+    port: String,
+    events: mpsc::Receiver<Event>,
+    _connection: midir::MidiInputConnection<()>,
+}
+
+impl Input {
+    /// Connects to the input port named `port`. `ctx` is repainted on every note, so the
+    /// events are picked up even while the app is idle.
+    pub fn connect(port: &str, ctx: egui::Context) -> Result<Self, Box<dyn std::error::Error>> {
+        let input = midir::MidiInput::new(CLIENT_NAME)?;
+        let found = input
+            .ports()
+            .into_iter()
+            .find(|p| input.port_name(p).map_or(false, |name| name == port))
+            .ok_or_else(|| format!("MIDI port {port} is gone"))?;
+        let (sender, events) = mpsc::channel();
+        let connection = input.connect(
+            &found,
+            CLIENT_NAME,
+            move |_, message, _| {
+                if let Some(event) = Event::parse(message) {
+                    // The receiver only goes away with the connection.
+                    let _ = sender.send(event);
+                    ctx.request_repaint();
+                }
+            },
+            (),
+        )?;
+
+        Ok(Self {
+            port: port.to_owned(),
+            events,
+            _connection: connection,
+        })
+    }
+
+    /// Name of the connected port.
+    pub fn port(&self) -> &str {
+        &self.port
+    }
+
+    /// Events received since the last call.
+    pub fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.try_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_notes_on_any_channel() {
+        assert_eq!(
+            Event::parse(&[0x90, 60, 100]),
+            Some(Event::NoteOn {
+                note: 60,
+                velocity: 100
+            })
+        );
+        assert_eq!(
+            Event::parse(&[0x9f, 61, 1]),
+            Some(Event::NoteOn {
+                note: 61,
+                velocity: 1
+            })
+        );
+        assert_eq!(
+            Event::parse(&[0x80, 60, 64]),
+            Some(Event::NoteOff { note: 60 })
+        );
+        assert_eq!(
+            Event::parse(&[0x83, 60, 0]),
+            Some(Event::NoteOff { note: 60 })
+        );
+        // Note on with velocity 0 is a note off.
+        assert_eq!(
+            Event::parse(&[0x90, 60, 0]),
+            Some(Event::NoteOff { note: 60 })
+        );
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        // Control change, pitch bend, clock.
+        assert_eq!(Event::parse(&[0xb0, 7, 100]), None);
+        assert_eq!(Event::parse(&[0xe0, 0, 64]), None);
+        assert_eq!(Event::parse(&[0xf8]), None);
+        // Without a status byte, as with running status, a message isn't parsed.
+        assert_eq!(Event::parse(&[60, 100]), None);
+        assert_eq!(Event::parse(&[0x90]), None);
+        assert_eq!(Event::parse(&[]), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Frequencies of musical notes, in twelve-tone equal temperament.
+
+/// Default pitch of A4, in Hz.
+pub const DEFAULT_REFERENCE_A: f64 = 440.0;
+
+/// MIDI note number of A4.
+const A4: i32 = 69;
+
+/// Frequency of the note named `name`, e.g. "A4", "C#3" or "Bb-1", with A4 tuned to
+/// `reference_a` Hz.
+///
+/// Names are a letter from A to G, any number of sharps (`#`, `♯`) or flats (`b`, `♭`), and an
+/// octave number in scientific pitch notation, where C4 is middle C. Returns `None` if `name`
+/// isn't a note.
+pub fn frequency(name: &str, reference_a: f64) -> Option<f64> {
+    let mut chars = name.trim().chars();
+    let semitone = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let octave_start = rest
+        .find(|c: char| c == '-' || c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (accidentals, octave) = rest.split_at(octave_start);
+    let accidental = accidentals.chars().try_fold(0_i32, |sum, c| match c {
+        '#' | '♯' => sum.checked_add(1),
+        'b' | '♭' => sum.checked_sub(1),
+        _ => None,
+    })?;
+    let octave: i32 = octave.parse().ok()?;
+
+    // Octaves far out of hearing range don't fit a note number.
+    let note = octave
+        .checked_add(1)?
+        .checked_mul(12)?
+        .checked_add(semitone)?
+        .checked_add(accidental)?;
+    Some(midi_frequency(note, reference_a))
+}
+
+/// Frequency of MIDI note number `note`, with A4 (note 69) tuned to `reference_a` Hz.
+pub fn midi_frequency(note: i32, reference_a: f64) -> f64 {
+    reference_a * 2_f64.powf((f64::from(note) - f64::from(A4)) / 12.0)
+}
+
+/// Name of MIDI note number `note`, e.g. "C4" for middle C (60). Sharps are used for the black
+/// keys.
+#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+pub fn name(note: i32) -> String {
+    const NAMES: [&str; 12] = [
+        "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+    ];
+    #[allow(clippy::cast_sign_loss)]
+    let semitone = note.rem_euclid(12) as usize;
+
+    format!("{}{}", NAMES[semitone], note.div_euclid(12) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(name: &str, reference_a: f64, expected: f64) {
+        let f = frequency(name, reference_a).unwrap_or_else(|| panic!("{name} is a note"));
+        assert!((f - expected).abs() < 1e-6, "{name}: {f} != {expected}");
+    }
+
+    #[test]
+    fn parses_notes() {
+        assert_close("A4", DEFAULT_REFERENCE_A, 440.0);
+        assert_close(" a4 ", DEFAULT_REFERENCE_A, 440.0);
+        assert_close("C4", DEFAULT_REFERENCE_A, 261.625_565);
+        assert_close("C#3", DEFAULT_REFERENCE_A, 138.591_315);
+        assert_close("C♯3", DEFAULT_REFERENCE_A, 138.591_315);
+        assert_close("Db3", DEFAULT_REFERENCE_A, 138.591_315);
+        assert_close("D♭3", DEFAULT_REFERENCE_A, 138.591_315);
+        assert_close("Bb-1", DEFAULT_REFERENCE_A, 14.567_618);
+        assert_close("B##3", DEFAULT_REFERENCE_A, 277.182_631);
+        assert_close("A5", 432.0, 864.0);
+        assert_close("E4", 432.0, 432.0 * 2_f64.powf(-5.0 / 12.0));
+    }
+
+    #[test]
+    fn rejects_non_notes() {
+        for name in [
+            "",
+            "H4",
+            "A",
+            "A#",
+            "A#x",
+            "4",
+            "Ax4",
+            "A4.5",
+            "A999999999",
+            "A-999999999",
+        ] {
+            assert_eq!(frequency(name, DEFAULT_REFERENCE_A), None, "{name}");
+        }
+    }
+}
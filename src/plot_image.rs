@@ -0,0 +1,269 @@
+//! Rendering plots to images, so they can be exported as PNG files.
+//!
+//! egui can't draw offscreen, so the plot is redrawn here from its data, in a similar style.
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 500;
+/// Space around the plot area, for the title and tick labels: left, right, top and bottom.
+const MARGINS: [f32; 4] = [80.0, 30.0, 60.0, 50.0];
+const TEXT_SIZE: f32 = 16.0;
+const TITLE_SIZE: f32 = 24.0;
+/// Approximate number of grid lines along each axis.
+const TICKS: f64 = 8.0;
+
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GRID: Rgba<u8> = Rgba([220, 220, 220, 255]);
+const FRAME: Rgba<u8> = Rgba([120, 120, 120, 255]);
+const TEXT: Rgba<u8> = Rgba([30, 30, 30, 255]);
+const LINE: Rgba<u8> = Rgba([31, 119, 180, 255]);
+
+/// Texts shown around an exported plot.
+pub struct PlotLabels {
+    pub title: String,
+    pub x: String,
+    pub y: String,
+}
+
+/// Renders `points` as a line plot.
+pub fn render(labels: &PlotLabels, points: &[[f64; 2]]) -> RgbaImage {
+    let fonts = egui::FontDefinitions::default();
+    let font = FontRef::try_from_slice(&fonts.font_data["Ubuntu-Light"].font)
+        .expect("the font bundled with egui is valid");
+    let mut image = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+    #[allow(clippy::cast_precision_loss)]
+    let area = Area {
+        left: MARGINS[0],
+        right: WIDTH as f32 - MARGINS[1],
+        top: MARGINS[2],
+        bottom: HEIGHT as f32 - MARGINS[3],
+        bounds: Bounds::of(points),
+    };
+
+    for x in ticks(area.bounds.x_min, area.bounds.x_max) {
+        let (px, _) = area.position([x, area.bounds.y_min]);
+        draw_line(&mut image, (px, area.top), (px, area.bottom), GRID);
+        let label = egui::emath::round_to_decimals(x, 5).to_string();
+        draw_text(
+            &mut image,
+            &font,
+            TEXT_SIZE,
+            &label,
+            (px, area.bottom + 20.0),
+            0.5,
+        );
+    }
+    for y in ticks(area.bounds.y_min, area.bounds.y_max) {
+        let (_, py) = area.position([area.bounds.x_min, y]);
+        draw_line(&mut image, (area.left, py), (area.right, py), GRID);
+        let label = egui::emath::round_to_decimals(y, 5).to_string();
+        draw_text(
+            &mut image,
+            &font,
+            TEXT_SIZE,
+            &label,
+            (area.left - 6.0, py + 5.0),
+            1.0,
+        );
+    }
+    for (from, to) in [
+        ((area.left, area.top), (area.right, area.top)),
+        ((area.right, area.top), (area.right, area.bottom)),
+        ((area.right, area.bottom), (area.left, area.bottom)),
+        ((area.left, area.bottom), (area.left, area.top)),
+    ] {
+        draw_line(&mut image, from, to, FRAME);
+    }
+
+    for pair in points.windows(2) {
+        draw_line(
+            &mut image,
+            area.position(pair[0]),
+            area.position(pair[1]),
+            LINE,
+        );
+    }
+
+    // Same layout as in the app: the y label above the plot, the x label below it on the right.
+    #[allow(clippy::cast_precision_loss)]
+    let center = WIDTH as f32 / 2.0;
+    draw_text(
+        &mut image,
+        &font,
+        TITLE_SIZE,
+        &labels.title,
+        (center, 28.0),
+        0.5,
+    );
+    draw_text(
+        &mut image,
+        &font,
+        TEXT_SIZE,
+        &labels.y,
+        (area.left, area.top - 10.0),
+        0.0,
+    );
+    #[allow(clippy::cast_precision_loss)]
+    let baseline = HEIGHT as f32 - 8.0;
+    draw_text(
+        &mut image,
+        &font,
+        TEXT_SIZE,
+        &labels.x,
+        (area.right, baseline),
+        1.0,
+    );
+
+    image
+}
+
+/// Range of the data shown in the plot.
+struct Bounds {
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+}
+
+impl Bounds {
+    /// Bounds of `points`, with some space above and below the data, like egui's plots.
+    fn of(points: &[[f64; 2]]) -> Self {
+        let (mut x_min, mut x_max, mut y_min, mut y_max) = points.iter().fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(x_min, x_max, y_min, y_max), [x, y]| {
+                (x_min.min(*x), x_max.max(*x), y_min.min(*y), y_max.max(*y))
+            },
+        );
+        if x_min >= x_max || x_min.is_nan() {
+            (x_min, x_max) = (x_min.min(0.0) - 1.0, x_max.max(0.0) + 1.0);
+        }
+        if y_min >= y_max || y_min.is_nan() {
+            (y_min, y_max) = (y_min.min(0.0) - 1.0, y_max.max(0.0) + 1.0);
+        }
+        let margin = (y_max - y_min) * 0.05;
+
+        Self {
+            x_min,
+            x_max,
+            y_min: y_min - margin,
+            y_max: y_max + margin,
+        }
+    }
+}
+
+/// The part of the image the data is drawn in.
+struct Area {
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+    bounds: Bounds,
+}
+
+impl Area {
+    /// Position of the data point `[x, y]` in the image.
+    fn position(&self, [x, y]: [f64; 2]) -> (f32, f32) {
+        let b = &self.bounds;
+        let fx = (x - b.x_min) / (b.x_max - b.x_min);
+        let fy = (y - b.y_min) / (b.y_max - b.y_min);
+        #[allow(clippy::cast_possible_truncation)]
+        (
+            self.left + (self.right - self.left) * fx as f32,
+            self.bottom - (self.bottom - self.top) * fy as f32,
+        )
+    }
+}
+
+/// Round values between `min` and `max` to put grid lines at.
+fn ticks(min: f64, max: f64) -> impl Iterator<Item = f64> {
+    let rough = (max - min) / TICKS;
+    let magnitude = 10_f64.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap_or(rough);
+    let first = (min / step).ceil();
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let count = ((max / step).floor() - first + 1.0).max(0.0) as usize;
+    #[allow(clippy::cast_precision_loss)]
+    (0..count).map(move |i| (first + i as f64) * step)
+}
+
+/// Draws a one pixel wide line, clipped to the image.
+fn draw_line(image: &mut RgbaImage, (x0, y0): (f32, f32), (x1, y1): (f32, f32), color: Rgba<u8>) {
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    for i in 0..=steps as u32 {
+        #[allow(clippy::cast_precision_loss)]
+        let t = i as f32 / steps;
+        put_pixel(image, x0 + (x1 - x0) * t, y0 + (y1 - y0) * t, color, 1.0);
+    }
+}
+
+/// Draws `text` with its baseline at `y`. `align` is the fraction of the text's width left
+/// of `x`: 0 for left aligned, 0.5 for centered and 1 for right aligned.
+fn draw_text(
+    image: &mut RgbaImage,
+    font: &FontRef<'_>,
+    size: f32,
+    text: &str,
+    (x, y): (f32, f32),
+    align: f32,
+) {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut glyphs = vec![];
+    let mut caret = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(size, ab_glyph::point(caret, 0.0)));
+        caret += font.h_advance(id);
+        previous = Some(id);
+    }
+
+    let origin = ab_glyph::point(x - caret * align, y);
+    for mut glyph in glyphs {
+        glyph.position += origin;
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                #[allow(clippy::cast_precision_loss)]
+                put_pixel(
+                    image,
+                    bounds.min.x + gx as f32,
+                    bounds.min.y + gy as f32,
+                    TEXT,
+                    coverage,
+                );
+            });
+        }
+    }
+}
+
+/// Blends `color` into the pixel at `(x, y)` with the given `alpha`, if it is inside the image.
+fn put_pixel(image: &mut RgbaImage, x: f32, y: f32, color: Rgba<u8>, alpha: f32) {
+    let (x, y) = (x.round(), y.round());
+    #[allow(clippy::cast_precision_loss)]
+    if x < 0.0 || y < 0.0 || x >= image.width() as f32 || y >= image.height() as f32 {
+        return;
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    for (channel, target) in pixel.0.iter_mut().zip(color.0).take(3) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let blended = f32::from(*channel) * (1.0 - alpha) + f32::from(target) * alpha;
+        *channel = blended.round() as u8;
+    }
+}
@@ -0,0 +1,136 @@
+//! Clipping of the summed waveform, to study the harmonics that distortion generates.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SaturationKind {
+    /// Cuts the signal off sharply at the threshold.
+    #[default]
+    Hard,
+    /// Bends the signal smoothly towards the threshold, like an overdriven amplifier.
+    SoftTanh,
+}
+
+impl SaturationKind {
+    pub const ALL: [SaturationKind; 2] = [SaturationKind::Hard, SaturationKind::SoftTanh];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SaturationKind::Hard => "Hard",
+            SaturationKind::SoftTanh => "Soft (tanh)",
+        }
+    }
+}
+
+/// A memoryless nonlinearity: the signal is amplified by `drive`, then limited to
+/// `±threshold`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Saturation {
+    pub kind: SaturationKind,
+    pub drive: f64,
+    pub threshold: f64,
+}
+
+impl Default for Saturation {
+    fn default() -> Self {
+        Self {
+            kind: SaturationKind::default(),
+            drive: 2.0,
+            threshold: 1.0,
+        }
+    }
+}
+
+impl Saturation {
+    /// Saturates each of `samples` in place.
+    pub fn apply(&self, samples: &mut [f64]) {
+        if self.threshold <= 0.0 {
+            samples.fill(0.0);
+            return;
+        }
+
+        for s in samples {
+            let driven = self.drive * *s;
+            *s = match self.kind {
+                SaturationKind::Hard => driven.clamp(-self.threshold, self.threshold),
+                SaturationKind::SoftTanh => self.threshold * (driven / self.threshold).tanh(),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saturate(saturation: Saturation, samples: &[f64]) -> Vec<f64> {
+        let mut samples = samples.to_vec();
+        saturation.apply(&mut samples);
+        samples
+    }
+
+    #[test]
+    fn hard_clips_at_the_threshold() {
+        let saturation = Saturation {
+            kind: SaturationKind::Hard,
+            drive: 1.0,
+            threshold: 0.5,
+        };
+        // Below the threshold, a drive of 1 leaves the signal alone.
+        assert_eq!(
+            saturate(saturation, &[-2.0, -0.5, -0.25, 0.0, 0.25, 0.5, 2.0]),
+            [-0.5, -0.5, -0.25, 0.0, 0.25, 0.5, 0.5]
+        );
+
+        let driven = Saturation {
+            drive: 4.0,
+            ..saturation
+        };
+        assert_eq!(saturate(driven, &[-0.1, 0.1, 0.2]), [-0.4, 0.4, 0.5]);
+    }
+
+    #[test]
+    fn soft_clips_smoothly_towards_the_threshold() {
+        let saturation = Saturation {
+            kind: SaturationKind::SoftTanh,
+            drive: 1.0,
+            threshold: 0.5,
+        };
+        let samples: Vec<_> = (-100..=100).map(|i| f64::from(i) / 10.0).collect();
+        let saturated = saturate(saturation, &samples);
+
+        for (s, out) in samples.iter().zip(&saturated) {
+            assert!((out - 0.5 * (s / 0.5).tanh()).abs() < 1e-12);
+            assert!(out.abs() <= 0.5);
+        }
+        assert!(saturated.windows(2).all(|w| w[0] <= w[1]));
+        // Small signals pass nearly unchanged at a drive of 1.
+        let small = saturate(saturation, &[0.001])[0];
+        assert!((small - 0.001).abs() < 1e-8);
+    }
+
+    #[test]
+    fn is_symmetric() {
+        let samples: Vec<_> = (1..=50).map(|i| f64::from(i) / 10.0).collect();
+        let negated: Vec<_> = samples.iter().map(|s| -s).collect();
+        for kind in SaturationKind::ALL {
+            let saturation = Saturation {
+                kind,
+                ..Saturation::default()
+            };
+            for (p, n) in saturate(saturation, &samples)
+                .iter()
+                .zip(saturate(saturation, &negated))
+            {
+                assert_eq!(*p, -n);
+            }
+        }
+    }
+
+    #[test]
+    fn silences_without_a_threshold() {
+        let saturation = Saturation {
+            threshold: 0.0,
+            ..Saturation::default()
+        };
+        assert_eq!(saturate(saturation, &[-1.0, 0.5, 1.0]), [0.0; 3]);
+    }
+}
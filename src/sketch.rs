@@ -0,0 +1,107 @@
+//! Waveforms synthesized from a spectrum sketched on the spectrum plot, by an inverse FFT.
+
+use crate::fft;
+use crate::util::Cache;
+use rustfft::num_complex::Complex;
+
+/// Spectral lines placed by the user, and the waveform they add up to.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SpectralSketch {
+    /// `[frequency, magnitude]` of each line, sorted by frequency. Magnitudes are on the same
+    /// scale as the linear magnitude spectrum, so a line shows up in the spectrum at the height
+    /// it was placed at.
+    points: Vec<[f64; 2]>,
+
+    #[serde(skip)]
+    synthesis: Cache<Synthesis>,
+}
+
+/// A waveform synthesized from the sketch, and the settings it was synthesized with.
+struct Synthesis {
+    sample_rate: f64,
+    n_samples: usize,
+    samples: Vec<f64>,
+}
+
+impl SpectralSketch {
+    pub fn points(&self) -> &[[f64; 2]] {
+        &self.points
+    }
+
+    /// Adds a line at `frequency`, with the given `magnitude`.
+    pub fn add(&mut self, frequency: f64, magnitude: f64) {
+        let i = self.points.partition_point(|p| p[0] < frequency);
+        self.points.insert(i, [frequency, magnitude]);
+        self.synthesis.invalidate();
+    }
+
+    /// Removes the line closest to `frequency`, if there are any.
+    pub fn remove_nearest(&mut self, frequency: f64) {
+        if let Some(i) = (0..self.points.len()).min_by(|a, b| {
+            let distance = |i: &usize| (self.points[*i][0] - frequency).abs();
+            distance(a).total_cmp(&distance(b))
+        }) {
+            self.points.remove(i);
+            self.synthesis.invalidate();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.synthesis.invalidate();
+    }
+
+    /// Returns the waveform the sketch adds up to, synthesizing it only if the sketch or the
+    /// settings changed since the last call.
+    pub fn samples(&mut self, sample_rate: f64, n_samples: usize) -> &[f64] {
+        if let Some(s) = self.synthesis.get_mut() {
+            #[allow(clippy::float_cmp)]
+            if s.sample_rate != sample_rate || s.n_samples != n_samples {
+                self.synthesis.invalidate();
+            }
+        }
+
+        let points = &self.points;
+        &self
+            .synthesis
+            .get_or_init(|| Synthesis {
+                sample_rate,
+                n_samples,
+                samples: synthesize(points, sample_rate, n_samples),
+            })
+            .samples
+    }
+}
+
+/// Synthesizes `n_samples` samples of the signal with the spectral lines `points`.
+///
+/// Each line is moved to the nearest FFT bin, so that the signal is periodic in `n_samples`.
+/// All lines start at zero phase, and lines above the Nyquist frequency are left out.
+pub fn synthesize(points: &[[f64; 2]], sample_rate: f64, n_samples: usize) -> Vec<f64> {
+    if n_samples == 0 {
+        return vec![];
+    }
+
+    let mut buffer = vec![Complex::default(); n_samples];
+    #[allow(clippy::cast_precision_loss)]
+    let resolution = sample_rate / n_samples as f64;
+    for [frequency, magnitude] in points {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let bin = (frequency / resolution).round().max(0.0) as usize;
+        if bin > n_samples / 2 {
+            continue;
+        }
+        // The one-sided magnitude is split between `bin` and its mirror image, which is the
+        // same bin for DC and the Nyquist frequency.
+        if bin == 0 || bin * 2 == n_samples {
+            buffer[bin] += magnitude;
+        } else {
+            buffer[bin] += magnitude / 2.0;
+            buffer[n_samples - bin] += magnitude / 2.0;
+        }
+    }
+    fft::inverse(n_samples).process(&mut buffer);
+
+    buffer.iter().map(|c| c.re).collect()
+}
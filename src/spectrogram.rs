@@ -0,0 +1,113 @@
+//! Short-time Fourier transform of the generated waveform, shown as a heatmap.
+
+use crate::fft;
+use crate::window::WindowFunction;
+use rustfft::num_complex::Complex;
+
+/// Window sizes offered in the settings.
+pub const WINDOW_SIZES: [usize; 5] = [64, 128, 256, 512, 1024];
+
+/// Range of magnitudes shown, in dB below the loudest bin. Anything quieter is black.
+const DYNAMIC_RANGE_DB: f64 = 80.0;
+
+/// Everything besides the samples themselves that the spectrogram depends on.
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub sample_rate: f64,
+    /// The spectrogram is shown up to `sample_rate / fmax_scale`.
+    pub fmax_scale: f64,
+    pub window: WindowFunction,
+    /// Number of samples in each transformed segment.
+    pub window_size: usize,
+    /// Fraction of each segment shared with the next one, in `[0, 1)`.
+    pub overlap: f64,
+}
+
+/// The computed spectrogram, ready to be drawn in a plot.
+pub struct Spectrogram {
+    pub texture: egui::TextureHandle,
+    /// Time covered by the image, in seconds.
+    pub duration: f64,
+    /// Frequency covered by the image, in Hz.
+    pub max_frequency: f64,
+}
+
+impl Spectrogram {
+    /// Computes the spectrogram of `samples` and uploads it as a texture.
+    ///
+    /// Returns `None` if there are fewer samples than fit in a single window.
+    pub fn new(ctx: &egui::Context, samples: &[f64], settings: &Settings) -> Option<Self> {
+        let size = settings.window_size;
+        if size == 0 || samples.len() < size {
+            return None;
+        }
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let hop = ((size as f64 * (1.0 - settings.overlap)).round() as usize).max(1);
+        #[allow(clippy::cast_precision_loss)]
+        let resolution = settings.sample_rate / size as f64;
+        let fmax = settings.sample_rate / settings.fmax_scale;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let n_bins = ((fmax / resolution).ceil() as usize).clamp(1, size / 2);
+
+        let window = settings.window.coefficients(size);
+        #[allow(clippy::cast_precision_loss)]
+        let norm = size as f64 * settings.window.coherent_gain(size);
+        let fft = fft::forward(size);
+        let columns: Vec<Vec<f64>> = (0..=samples.len() - size)
+            .step_by(hop)
+            .map(|start| {
+                let mut buffer: Vec<_> = samples[start..start + size]
+                    .iter()
+                    .zip(&window)
+                    .map(|(s, w)| Complex::new(s * w, 0.0))
+                    .collect();
+                fft.process(&mut buffer);
+                buffer[..n_bins]
+                    .iter()
+                    .map(|c| 20.0 * (c.norm() / norm).log10())
+                    .collect()
+            })
+            .collect();
+
+        let loudest = columns
+            .iter()
+            .flatten()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let width = columns.len();
+        // Highest frequencies go on top.
+        let pixels = (0..n_bins)
+            .rev()
+            .flat_map(|bin| columns.iter().map(move |column| column[bin]))
+            .map(|db| heat_color((db - loudest + DYNAMIC_RANGE_DB) / DYNAMIC_RANGE_DB))
+            .collect();
+        let image = egui::ColorImage {
+            size: [width, n_bins],
+            pixels,
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        Some(Self {
+            texture: ctx.load_texture("spectrogram", image, egui::TextureOptions::NEAREST),
+            duration: ((width - 1) * hop + size) as f64 / settings.sample_rate,
+            max_frequency: n_bins as f64 * resolution,
+        })
+    }
+}
+
+/// Maps `value` in `[0, 1]` onto a black-red-yellow-white color scale. Values outside are clamped.
+fn heat_color(value: f64) -> egui::Color32 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let v = if value.is_nan() { 0.0 } else { value };
+    egui::Color32::from_rgb(
+        channel(3.0 * v),
+        channel(3.0 * v - 1.0),
+        channel(3.0 * v - 2.0),
+    )
+}
@@ -0,0 +1,568 @@
+//! Computing the spectrum of the generated waveform.
+//!
+//! On native targets the computation runs on a background worker thread, so that large
+//! transforms don't stall the UI. On the web it is done synchronously.
+
+use crate::fft;
+use crate::util;
+use crate::window::WindowFunction;
+use rustfft::num_complex::Complex;
+use std::f64::consts::{PI, TAU};
+
+/// Lowest magnitude shown in decibel mode, so that empty bins don't end up at -inf.
+const DB_FLOOR: f64 = -120.0;
+
+/// Everything besides the samples themselves that the spectrum depends on.
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub sample_rate: f64,
+    /// The spectrum is shown up to `sample_rate / fmax_scale`.
+    pub fmax_scale: f64,
+    pub window: WindowFunction,
+    /// Length of the transform, before zero padding. The samples are cut off at, or padded with
+    /// zeros to, this length, so the spectrum resolution is `sample_rate / fft_size` (before
+    /// zero padding) however many samples there are.
+    ///
+    /// `None` picks the next power of two at or above the number of samples, which rustfft is
+    /// fastest at.
+    pub fft_size: Option<usize>,
+    /// The windowed samples are padded with zeros to `zero_pad_factor` times the FFT size.
+    ///
+    /// This interpolates between the bins of the unpadded spectrum, making it look smoother,
+    /// but doesn't add any real frequency resolution.
+    pub zero_pad_factor: usize,
+    /// How the magnitudes are scaled. Doesn't apply in PSD mode.
+    pub norm: SpectrumNorm,
+    pub db: bool,
+    /// Show the (two-sided) power spectral density instead of the magnitude.
+    pub psd: bool,
+    /// Show negative frequencies too, from the upper half of the FFT, and split the magnitude
+    /// of each sinusoid between its positive and negative frequency.
+    pub two_sided: bool,
+    pub peak_threshold: f64,
+    /// Length of the moving average applied to the spectrum, in bins. 1 disables smoothing.
+    pub smoothing: usize,
+    pub unwrap_phase: bool,
+}
+
+/// Scaling of the magnitude spectrum.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SpectrumNorm {
+    /// `|X| / N`, so that a sinusoid reads half its amplitude in each of its two bins.
+    N,
+    /// `|X| / (N / 2)`, so that a sinusoid reads its amplitude with the rectangular window.
+    HalfN,
+    /// Raw `|X|`, which grows with the number of samples.
+    None,
+    /// One-sided and corrected for the coherent gain of the window, so that a sinusoid reads its
+    /// amplitude with any window.
+    #[default]
+    Amplitude,
+}
+
+impl SpectrumNorm {
+    pub const ALL: [SpectrumNorm; 4] = [
+        SpectrumNorm::Amplitude,
+        SpectrumNorm::N,
+        SpectrumNorm::HalfN,
+        SpectrumNorm::None,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpectrumNorm::N => "÷ N",
+            SpectrumNorm::HalfN => "÷ N/2",
+            SpectrumNorm::None => "None",
+            SpectrumNorm::Amplitude => "Amplitude",
+        }
+    }
+
+    /// What the magnitudes stand for, for labelling the axis.
+    pub fn quantity(self) -> &'static str {
+        match self {
+            SpectrumNorm::N => "|X|/N",
+            SpectrumNorm::HalfN => "|X|/(N/2)",
+            SpectrumNorm::None => "|X|",
+            SpectrumNorm::Amplitude => "amplitude",
+        }
+    }
+
+    /// Scales the magnitude `|X|` of a bin of the transform of `n` samples, windowed with a
+    /// window of coherent gain `gain`. `sides` is 2 if the bin also stands for its mirror image,
+    /// which isn't shown, and 1 otherwise.
+    fn apply(self, magnitude: f64, sides: f64, n: f64, gain: f64) -> f64 {
+        match self {
+            SpectrumNorm::N => magnitude / n,
+            SpectrumNorm::HalfN => magnitude / (n / 2.0),
+            SpectrumNorm::None => magnitude,
+            SpectrumNorm::Amplitude => sides * magnitude / (n * gain),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Spectrum {
+    /// `[frequency, magnitude]` of each bin, or `[frequency, power density]` in PSD mode.
+    pub points: Vec<[f64; 2]>,
+    pub peaks: Vec<[f64; 2]>,
+    /// `[frequency, phase]` of each bin, in radians.
+    pub phase: Vec<[f64; 2]>,
+    /// Magnitude (or power density) of each bin in the spectra of the individual components,
+    /// keyed by their index in the component list.
+    pub contributions: Vec<(usize, Vec<f64>)>,
+}
+
+/// Computes the spectrum of `samples`.
+pub fn compute(samples: &[f64], settings: &Settings) -> Spectrum {
+    let fft_size = settings
+        .fft_size
+        .unwrap_or_else(|| samples.len().next_power_of_two());
+    let samples = &samples[..samples.len().min(fft_size)];
+    let n_samples = samples.len();
+    let fmax = settings.sample_rate / settings.fmax_scale;
+    #[allow(clippy::cast_precision_loss)]
+    let n = n_samples as f64;
+    let padded_len = fft_size * settings.zero_pad_factor.max(1);
+    #[allow(clippy::cast_precision_loss)]
+    let spectrum_resolution = settings.sample_rate / padded_len as f64;
+    let mut input: Vec<_> = samples
+        .iter()
+        .zip(settings.window.coefficients(n_samples))
+        .map(|(s, w)| s * w)
+        .collect();
+    input.resize(padded_len, 0.0);
+    let gain = settings.window.coherent_gain(n_samples);
+    let power_gain = settings.window.power_gain(n_samples);
+    let plan = fft::real_forward(padded_len);
+    let mut half = plan.make_output_vec();
+    plan.process(&mut input, &mut half)
+        .expect("the buffers are made for the plan");
+    // The input is real, so the bins above the Nyquist frequency, which are only shown when
+    // `fmax_scale` is below 2, mirror the ones below it.
+    let bin = |i: usize| -> Complex<f64> {
+        half.get(i)
+            .copied()
+            .unwrap_or_else(|| half[padded_len - i].conj())
+    };
+
+    // Bins above the middle stand for negative frequencies in a two-sided spectrum.
+    let half_len = padded_len / 2;
+    #[allow(clippy::cast_precision_loss)]
+    let frequency = |i: usize| {
+        if settings.two_sided && i > half_len {
+            -((padded_len - i) as f64) * spectrum_resolution
+        } else {
+            i as f64 * spectrum_resolution
+        }
+    };
+    let bins: Vec<_> = if settings.two_sided {
+        (half_len + 1..padded_len)
+            .chain(0..=half_len)
+            .filter(|i| frequency(*i).abs() < fmax)
+            .collect()
+    } else {
+        (0..padded_len)
+            .take_while(|i| frequency(*i) < fmax)
+            .collect()
+    };
+
+    let mut points: Vec<_> = bins
+        .iter()
+        .map(|&i| {
+            let c = bin(i);
+            // In a one-sided spectrum, all bins besides DC and Nyquist also stand for their
+            // mirror image.
+            let sides = if settings.two_sided || i == 0 || 2 * i == padded_len {
+                1.0
+            } else {
+                2.0
+            };
+            let value = if settings.psd {
+                c.norm_sqr() / (settings.sample_rate * n * power_gain)
+            } else {
+                settings.norm.apply(c.norm(), sides, n, gain)
+            };
+            [frequency(i), value]
+        })
+        .collect();
+    // Smoothing averages the linear values, as averaging decibels would pull the result towards
+    // the noise floor.
+    smooth(&mut points, settings.smoothing);
+    if settings.db {
+        let factor = if settings.psd { 10.0 } else { 20.0 };
+        for [_, value] in &mut points {
+            *value = (factor * value.log10()).max(DB_FLOOR);
+        }
+    }
+    let mut phase: Vec<_> = bins.iter().map(|&i| [frequency(i), bin(i).arg()]).collect();
+    if settings.unwrap_phase {
+        unwrap(&mut phase);
+    }
+
+    Spectrum {
+        peaks: util::find_peaks(&points, settings.peak_threshold),
+        points,
+        phase,
+        contributions: vec![],
+    }
+}
+
+/// Computes the spectrum of `samples`, along with the spectra of the `components` they are the
+/// sum of, keyed by their index in the component list.
+pub fn compute_with_components(
+    samples: &[f64],
+    components: &[(usize, Vec<f64>)],
+    settings: &Settings,
+) -> Spectrum {
+    let mut spectrum = compute(samples, settings);
+    spectrum.contributions = components
+        .iter()
+        .map(|(i, samples)| {
+            let points = compute(samples, settings).points;
+            (*i, points.into_iter().map(|[_, value]| value).collect())
+        })
+        .collect();
+    spectrum
+}
+
+/// The largest value each bin has had across spectra, like the max hold of a spectrum analyzer.
+///
+/// Catches intermittent signals that single spectra may miss.
+#[derive(Default)]
+pub struct MaxHold {
+    points: Vec<[f64; 2]>,
+    /// Scaling of `points`, as `(norm, db, psd)`.
+    scale: Option<(SpectrumNorm, bool, bool)>,
+}
+
+impl MaxHold {
+    /// `[frequency, value]` of each bin.
+    pub fn points(&self) -> &[[f64; 2]] {
+        &self.points
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Raises the held bins to the values of `spectrum`, computed with `settings`.
+    ///
+    /// Starts over from `spectrum` when its bins or scaling differ from the held ones.
+    pub fn update(&mut self, spectrum: &Spectrum, settings: &Settings) {
+        let scale = Some((settings.norm, settings.db, settings.psd));
+        let same_bins = self.points.len() == spectrum.points.len()
+            && self
+                .points
+                .iter()
+                .zip(&spectrum.points)
+                .all(|([held, _], [f, _])| held == f);
+        if self.scale != scale || !same_bins {
+            self.points = spectrum.points.clone();
+            self.scale = scale;
+            return;
+        }
+        for ([_, held], [_, value]) in self.points.iter_mut().zip(&spectrum.points) {
+            *held = held.max(*value);
+        }
+    }
+}
+
+/// Replaces each value of `points` with the mean of the `window` values centered on it.
+///
+/// The window shrinks near the ends, where there aren't enough values on one side.
+fn smooth(points: &mut [[f64; 2]], window: usize) {
+    if window <= 1 || points.is_empty() {
+        return;
+    }
+
+    let values: Vec<_> = points.iter().map(|[_, v]| *v).collect();
+    let half = window / 2;
+    for (i, [_, v]) in points.iter_mut().enumerate() {
+        let range = &values[i.saturating_sub(half)..(i + window - half).min(values.len())];
+        #[allow(clippy::cast_precision_loss)]
+        let mean = range.iter().sum::<f64>() / range.len() as f64;
+        *v = mean;
+    }
+}
+
+/// Removes the 2π jumps between consecutive phase values.
+fn unwrap(phase: &mut [[f64; 2]]) {
+    let mut offset = 0.0;
+    let mut previous = None;
+    for [_, p] in phase.iter_mut() {
+        let raw = *p;
+        if let Some(previous) = previous {
+            let delta = raw - previous;
+            if delta > PI {
+                offset -= TAU;
+            } else if delta < -PI {
+                offset += TAU;
+            }
+        }
+        previous = Some(raw);
+        *p = raw + offset;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use worker::Job;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod worker {
+    use super::{compute, compute_with_components, Settings, Spectrum};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+
+    struct Request {
+        samples: Vec<f64>,
+        components: Vec<(usize, Vec<f64>)>,
+        right: Option<Vec<f64>>,
+        settings: Settings,
+        cancelled: Arc<AtomicBool>,
+        reply: Sender<(Spectrum, Option<Spectrum>)>,
+        ctx: egui::Context,
+    }
+
+    /// A single long-lived worker, so that its FFT plan cache survives between jobs.
+    static WORKER: once_cell::sync::Lazy<Mutex<Sender<Request>>> =
+        once_cell::sync::Lazy::new(|| {
+            let (tx, rx) = mpsc::channel::<Request>();
+            std::thread::Builder::new()
+                .name("spectrum".to_owned())
+                .spawn(move || {
+                    for request in rx {
+                        if request.cancelled.load(Ordering::Relaxed) {
+                            continue;
+                        }
+                        let spectrum = compute_with_components(
+                            &request.samples,
+                            &request.components,
+                            &request.settings,
+                        );
+                        let right = request
+                            .right
+                            .map(|samples| compute(&samples, &request.settings));
+                        if !request.cancelled.load(Ordering::Relaxed) {
+                            // The receiver may be gone already, in which case nobody cares.
+                            request.reply.send((spectrum, right)).ok();
+                            request.ctx.request_repaint();
+                        }
+                    }
+                })
+                .expect("Could not spawn spectrum worker thread");
+            Mutex::new(tx)
+        });
+
+    /// A spectrum being computed in the background, with that of the right channel if the
+    /// signal is stereo.
+    ///
+    /// Dropping the job cancels it.
+    pub struct Job {
+        result: Receiver<(Spectrum, Option<Spectrum>)>,
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl Job {
+        /// Queues computation of the spectrum of `samples` and its `components`, as in
+        /// [`compute_with_components`], and of the `right` channel. `ctx` is repainted once it's
+        /// done.
+        pub fn spawn(
+            samples: Vec<f64>,
+            components: Vec<(usize, Vec<f64>)>,
+            right: Option<Vec<f64>>,
+            settings: Settings,
+            ctx: egui::Context,
+        ) -> Self {
+            let (reply, result) = mpsc::channel();
+            let cancelled = Arc::new(AtomicBool::new(false));
+            WORKER
+                .lock()
+                .expect("Could not get lock on WORKER")
+                .send(Request {
+                    samples,
+                    components,
+                    right,
+                    settings,
+                    cancelled: cancelled.clone(),
+                    reply,
+                    ctx,
+                })
+                .expect("Spectrum worker thread is gone");
+
+            Self { result, cancelled }
+        }
+
+        /// Returns the spectrum and that of the right channel, if they're ready.
+        pub fn try_take(&self) -> Option<(Spectrum, Option<Spectrum>)> {
+            self.result.try_recv().ok()
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Plain settings for tests: the amplitude spectrum up to the Nyquist frequency, with a
+/// rectangular window and no padding, smoothing or peaks.
+#[cfg(test)]
+pub fn test_settings(sample_rate: f64) -> Settings {
+    Settings {
+        sample_rate,
+        fmax_scale: 2.0,
+        window: WindowFunction::Rectangular,
+        fft_size: None,
+        zero_pad_factor: 1,
+        norm: SpectrumNorm::Amplitude,
+        db: false,
+        psd: false,
+        two_sided: false,
+        peak_threshold: f64::INFINITY,
+        smoothing: 1,
+        unwrap_phase: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_top_reads_true_amplitude() {
+        let sample_rate = 1000.0;
+        // Halfway between two bins, where the scalloping loss is largest.
+        let frequency = 100.5;
+        let samples: Vec<_> = (0..1000)
+            .map(|i| (TAU * frequency * f64::from(i) / sample_rate).sin())
+            .collect();
+        let settings = Settings {
+            window: WindowFunction::FlatTop,
+            ..test_settings(sample_rate)
+        };
+
+        let peak = compute(&samples, &settings)
+            .points
+            .iter()
+            .map(|[_, m]| *m)
+            .fold(0.0, f64::max);
+        assert!((peak - 1.0).abs() < 0.01, "peak = {peak}");
+    }
+
+    #[test]
+    fn amplitude_norm_reads_unit_sine_as_one() {
+        let sample_rate = 1000.0;
+        // 50 whole periods in the samples.
+        let samples: Vec<_> = (0..1000)
+            .map(|i| (TAU * 50.0 * f64::from(i) / sample_rate).sin())
+            .collect();
+        let mut settings = Settings {
+            fft_size: Some(samples.len()),
+            ..test_settings(sample_rate)
+        };
+        let peak = |settings: &Settings| {
+            compute(&samples, settings)
+                .points
+                .iter()
+                .map(|[_, m]| *m)
+                .fold(0.0, f64::max)
+        };
+
+        assert!((peak(&settings) - 1.0).abs() < 1e-9);
+        settings.norm = SpectrumNorm::N;
+        assert!((peak(&settings) - 0.5).abs() < 1e-9);
+        settings.norm = SpectrumNorm::None;
+        assert!((peak(&settings) - 500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn max_hold_keeps_the_largest_value_of_each_bin() {
+        let settings = test_settings(1000.0);
+        let spectrum = |values: [f64; 3]| Spectrum {
+            points: values
+                .into_iter()
+                .zip([0.0, 1.0, 2.0])
+                .map(|(value, f)| [f, value])
+                .collect(),
+            ..Spectrum::default()
+        };
+        let mut hold = MaxHold::default();
+
+        hold.update(&spectrum([1.0, 0.0, 0.5]), &settings);
+        hold.update(&spectrum([0.0, 2.0, 0.25]), &settings);
+        assert_eq!(hold.points(), [[0.0, 1.0], [1.0, 2.0], [2.0, 0.5]]);
+
+        // Values in dB can't be compared with magnitudes.
+        let db = Settings {
+            db: true,
+            ..settings
+        };
+        hold.update(&spectrum([-6.0, -6.0, -6.0]), &db);
+        assert_eq!(hold.points(), [[0.0, -6.0], [1.0, -6.0], [2.0, -6.0]]);
+    }
+
+    #[test]
+    fn smooths_before_converting_to_db() {
+        let sample_rate = 1000.0;
+        let samples: Vec<_> = (0..1000)
+            .map(|i| (TAU * 50.0 * f64::from(i) / sample_rate).sin())
+            .collect();
+        let linear = Settings {
+            smoothing: 5,
+            ..test_settings(sample_rate)
+        };
+        let db = Settings { db: true, ..linear };
+
+        let linear = compute(&samples, &linear).points;
+        let db = compute(&samples, &db).points;
+        for ([_, m], [_, d]) in linear.iter().zip(&db) {
+            assert!((d - (20.0 * m.log10()).max(DB_FLOOR)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn real_fft_matches_complex_fft() {
+        for (len, fmax_scale) in [(1000, 1.0), (999, 1.0), (1024, 2.0)] {
+            #[allow(clippy::cast_precision_loss)]
+            let samples: Vec<_> = (0..len)
+                .map(|i| {
+                    let t = i as f64;
+                    (0.3 * t).sin() + 0.5 * (1.7 * t).cos() + 0.1
+                })
+                .collect();
+            let settings = Settings {
+                fmax_scale,
+                window: WindowFunction::Hann,
+                fft_size: Some(len),
+                zero_pad_factor: 2,
+                ..test_settings(1000.0)
+            };
+            let spectrum = compute(&samples, &settings);
+
+            let mut buffer: Vec<_> = samples
+                .iter()
+                .zip(settings.window.coefficients(len))
+                .map(|(s, w)| Complex::new(s * w, 0.0))
+                .collect();
+            buffer.resize(2 * len, Complex::default());
+            fft::forward(2 * len).process(&mut buffer);
+            #[allow(clippy::cast_precision_loss)]
+            let scale = len as f64 * settings.window.coherent_gain(len);
+
+            if fmax_scale == 1.0 {
+                assert_eq!(spectrum.points.len(), buffer.len());
+            }
+            for (i, [_, m]) in spectrum.points.iter().enumerate() {
+                let sides = if i == 0 || 2 * i == buffer.len() {
+                    1.0
+                } else {
+                    2.0
+                };
+                let expected = sides * buffer[i].norm() / scale;
+                assert!((m - expected).abs() < 1e-9, "bin {i}: {m} != {expected}");
+            }
+        }
+    }
+}
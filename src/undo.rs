@@ -0,0 +1,164 @@
+//! Undo/redo by comparing snapshots of the edited state between frames.
+
+/// How many undo steps are kept.
+const MAX_UNDO_STEPS: usize = 100;
+
+pub struct UndoStack<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    /// State at the end of the last frame, or after the last undo or redo. It is only cloned
+    /// again when the state changes.
+    last: Option<T>,
+    /// Whether the last change happened during an interaction that is still going on.
+    in_gesture: bool,
+}
+
+impl<T: Clone + PartialEq> UndoStack<T> {
+    pub fn new() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+            last: None,
+            in_gesture: false,
+        }
+    }
+
+    /// Records an undo step if `current` changed since the last frame.
+    ///
+    /// `interacting` tells whether the user is in the middle of something (dragging a value,
+    /// typing into a text field). All changes made during a single interaction are coalesced
+    /// into one step.
+    pub fn end_frame(&mut self, current: &T, interacting: bool) {
+        let Some(last) = &mut self.last else {
+            self.last = Some(current.clone());
+            return;
+        };
+        if last != current {
+            let previous = std::mem::replace(last, current.clone());
+            if !self.in_gesture {
+                self.undo.push(previous);
+                if self.undo.len() > MAX_UNDO_STEPS {
+                    self.undo.remove(0);
+                }
+                self.redo.clear();
+            }
+            self.in_gesture = interacting;
+        } else if !interacting {
+            self.in_gesture = false;
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Restores the previous state into `current`. Returns whether there was anything to undo.
+    pub fn undo(&mut self, current: &mut T) -> bool {
+        let stepped = Self::step(&mut self.undo, &mut self.redo, current);
+        if stepped {
+            self.last = Some(current.clone());
+        }
+        stepped
+    }
+
+    /// Re-applies the last undone change to `current`. Returns whether there was anything to redo.
+    pub fn redo(&mut self, current: &mut T) -> bool {
+        let stepped = Self::step(&mut self.redo, &mut self.undo, current);
+        if stepped {
+            self.last = Some(current.clone());
+        }
+        stepped
+    }
+
+    fn step(from: &mut Vec<T>, to: &mut Vec<T>, current: &mut T) -> bool {
+        match from.pop() {
+            Some(state) => {
+                to.push(std::mem::replace(current, state));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_and_redoes_changes() {
+        let mut stack = UndoStack::new();
+        let mut state = 0;
+        stack.end_frame(&state, false);
+        assert!(!stack.can_undo());
+
+        for value in [1, 2] {
+            state = value;
+            stack.end_frame(&state, false);
+        }
+        // Frames without changes don't add steps.
+        stack.end_frame(&state, false);
+
+        assert!(stack.undo(&mut state));
+        assert_eq!(state, 1);
+        stack.end_frame(&state, false);
+        assert!(stack.undo(&mut state));
+        assert_eq!(state, 0);
+        assert!(!stack.undo(&mut state));
+
+        assert!(stack.redo(&mut state));
+        assert_eq!(state, 1);
+        stack.end_frame(&state, false);
+        // A new change drops what could be redone.
+        state = 5;
+        stack.end_frame(&state, false);
+        assert!(!stack.can_redo());
+        assert!(stack.undo(&mut state));
+        assert_eq!(state, 1);
+    }
+
+    #[test]
+    fn coalesces_changes_of_one_interaction() {
+        let mut stack = UndoStack::new();
+        let mut state = 0;
+        stack.end_frame(&state, false);
+
+        // A drag changes the value over several frames, and ends with a frame without change.
+        for value in [1, 2, 3] {
+            state = value;
+            stack.end_frame(&state, true);
+        }
+        stack.end_frame(&state, false);
+        state = 4;
+        stack.end_frame(&state, false);
+
+        assert!(stack.undo(&mut state));
+        assert_eq!(state, 3);
+        assert!(stack.undo(&mut state));
+        assert_eq!(state, 0);
+        assert!(!stack.can_undo());
+    }
+
+    #[test]
+    fn keeps_at_most_max_steps() {
+        let mut stack = UndoStack::new();
+        let mut state = 0;
+        stack.end_frame(&state, false);
+        for value in 1..=MAX_UNDO_STEPS + 10 {
+            state = value;
+            stack.end_frame(&state, false);
+        }
+
+        let mut steps = 0;
+        while stack.undo(&mut state) {
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_UNDO_STEPS);
+        // The oldest states are dropped first.
+        assert_eq!(state, 10);
+    }
+}
@@ -1,36 +1,384 @@
+use crate::spectrum::Spectrum;
+
 /// A dead-simple cache implementation
+///
+/// Every invalidation bumps a generation counter, so that readers can tell whether the cache
+/// changed since they last looked at it.
 pub struct Cache<T> {
     data: Option<T>,
+    generation: u64,
 }
 
 impl<T> Cache<T> {
-    pub fn new(data: T) -> Self {
-        Self { data: Some(data) }
-    }
-
     pub fn get_or_init(&mut self, init: impl FnOnce() -> T) -> &T {
         self.data.get_or_insert_with(init)
     }
 
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.data.as_mut()
+    }
+
+    /// Takes the cached data out, leaving the cache invalidated.
+    pub fn take(&mut self) -> Option<T> {
+        self.generation += 1;
+        self.data.take()
+    }
+
     pub fn invalidate(&mut self) {
         self.data = None;
+        self.generation += 1;
     }
 
     pub fn is_valid(&self) -> bool {
         self.data.is_some()
     }
+
+    /// Number of times the cache has been invalidated.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl<T: SizeBytes> Cache<T> {
+    /// Estimated memory held by the cached data, or 0 if there is none.
+    pub fn size_bytes(&self) -> usize {
+        self.data.as_ref().map_or(0, T::size_bytes)
+    }
+}
+
+/// Estimate of the memory a value holds, counting the contents of its vectors.
+pub trait SizeBytes {
+    fn size_bytes(&self) -> usize;
+}
+
+impl<T> SizeBytes for Vec<T> {
+    fn size_bytes(&self) -> usize {
+        self.len() * std::mem::size_of::<T>()
+    }
+}
+
+impl<T: SizeBytes> SizeBytes for Option<T> {
+    fn size_bytes(&self) -> usize {
+        self.as_ref().map_or(0, T::size_bytes)
+    }
 }
 
 impl<T> Default for Cache<T> {
     fn default() -> Self {
         Self {
             data: Option::default(),
+            generation: 0,
         }
     }
 }
 
 #[derive(Default)]
 pub struct PlotData {
+    /// Sample rate of `waveform`. Lower than the one set when decimating.
+    pub sample_rate: f64,
     pub waveform: Vec<[f64; 2]>,
-    pub spectrum: Vec<[f64; 2]>,
+    /// `waveform`, reduced to the points that are drawn.
+    pub display_waveform: Vec<[f64; 2]>,
+    /// Waveforms of the individual components, keyed by their index in the component list.
+    /// Only filled in when individual waveforms are shown, and reduced for display like
+    /// `display_waveform`.
+    pub component_waveforms: Vec<(usize, Vec<[f64; 2]>)>,
+    /// Factor the waveform was scaled by to fit in `[-1, 1]`, if it was normalized.
+    pub normalization: Option<f64>,
+    /// The summed components sampled more densely, to draw the signal between the samples.
+    /// Only computed when that is shown, for signals made of components.
+    pub continuous: Option<Vec<[f64; 2]>>,
+    /// Set for stereo signals, whose left channel the other fields then hold.
+    pub right: Option<RightChannel>,
+    pub stats: Stats,
+    pub spectrum: Spectrum,
+    /// Only computed when the spectrogram is shown.
+    pub spectrogram: Option<crate::spectrogram::Spectrogram>,
+    /// Set while `spectrum` is still being computed in the background.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub spectrum_job: Option<crate::spectrum::Job>,
+}
+
+/// The right channel of a stereo signal.
+pub struct RightChannel {
+    pub waveform: Vec<[f64; 2]>,
+    /// `waveform`, reduced to the points that are drawn.
+    pub display_waveform: Vec<[f64; 2]>,
+    pub spectrum: Spectrum,
+}
+
+impl SizeBytes for Spectrum {
+    fn size_bytes(&self) -> usize {
+        self.points.size_bytes()
+            + self.peaks.size_bytes()
+            + self.phase.size_bytes()
+            + self
+                .contributions
+                .iter()
+                .map(|(_, values)| values.size_bytes())
+                .sum::<usize>()
+    }
+}
+
+impl SizeBytes for crate::spectrum::MaxHold {
+    fn size_bytes(&self) -> usize {
+        std::mem::size_of_val(self.points())
+    }
+}
+
+impl SizeBytes for PlotData {
+    fn size_bytes(&self) -> usize {
+        let component_waveforms: usize = self
+            .component_waveforms
+            .iter()
+            .map(|(_, points)| points.size_bytes())
+            .sum();
+        let right = self.right.as_ref().map_or(0, |right| {
+            right.waveform.size_bytes()
+                + right.display_waveform.size_bytes()
+                + right.spectrum.size_bytes()
+        });
+        // The spectrogram is held by the GPU, as an RGBA image.
+        let spectrogram = self.spectrogram.as_ref().map_or(0, |s| {
+            let [width, height] = s.texture.size();
+            width * height * 4
+        });
+
+        self.waveform.size_bytes()
+            + self.display_waveform.size_bytes()
+            + component_waveforms
+            + self.continuous.size_bytes()
+            + self.spectrum.size_bytes()
+            + right
+            + spectrogram
+    }
+}
+
+impl PlotData {
+    /// Whether the spectrum is still being computed.
+    pub fn spectrum_pending(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.spectrum_job.is_some();
+        #[cfg(target_arch = "wasm32")]
+        return false;
+    }
+
+    /// Picks up the spectra from the background job, if it has finished.
+    pub fn poll_spectrum(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((spectrum, right)) = self
+            .spectrum_job
+            .as_ref()
+            .and_then(crate::spectrum::Job::try_take)
+        {
+            self.spectrum = spectrum;
+            if let (Some(channel), Some(spectrum)) = (&mut self.right, right) {
+                channel.spectrum = spectrum;
+            }
+            self.spectrum_job = None;
+        }
+    }
+}
+
+/// Basic statistics of a waveform.
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    pub rms: f64,
+    /// Largest absolute sample value.
+    pub peak: f64,
+    /// Ratio of `peak` to `rms`, or `None` for silence.
+    pub crest_factor: Option<f64>,
+}
+
+impl Stats {
+    pub fn new(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let rms = (samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64).sqrt();
+        let peak = samples.iter().fold(0.0_f64, |peak, s| peak.max(s.abs()));
+        Self {
+            rms,
+            peak,
+            crest_factor: (rms > 0.0).then(|| peak / rms),
+        }
+    }
+}
+
+/// Rounds each of `samples` to the nearest of `2^bit_depth` evenly spaced levels spanning
+/// `[-1, 1]`. Samples outside of that range are clipped.
+pub fn quantize(samples: &mut [f64], bit_depth: u32) {
+    let step = 2.0 / (2_f64.powf(f64::from(bit_depth)) - 1.0);
+    for s in samples {
+        *s = ((s.clamp(-1.0, 1.0) + 1.0) / step).round() * step - 1.0;
+    }
+}
+
+/// Reduces `points` to at most `max_points` points for drawing.
+///
+/// The points are split into `max_points / 2` runs of (nearly) equal length, and only the
+/// lowest and highest point of each run are kept, in their original order, so that peaks stay
+/// visible.
+pub fn reduce_for_display(points: &[[f64; 2]], max_points: usize) -> Vec<[f64; 2]> {
+    let buckets = max_points / 2;
+    if points.len() <= max_points || buckets == 0 {
+        return points.to_vec();
+    }
+
+    let mut reduced = Vec::with_capacity(buckets * 2);
+    for b in 0..buckets {
+        let run = &points[b * points.len() / buckets..(b + 1) * points.len() / buckets];
+        let by_value = |a: &&[f64; 2], b: &&[f64; 2]| a[1].total_cmp(&b[1]);
+        let (Some(min), Some(max)) = (run.iter().min_by(by_value), run.iter().max_by(by_value))
+        else {
+            continue;
+        };
+        if min[0] <= max[0] {
+            reduced.push(*min);
+            if max[0] > min[0] {
+                reduced.push(*max);
+            }
+        } else {
+            reduced.extend([*max, *min]);
+        }
+    }
+    reduced
+}
+
+/// Finds local maxima of `points` whose value is at least `threshold`.
+pub fn find_peaks(points: &[[f64; 2]], threshold: f64) -> Vec<[f64; 2]> {
+    points
+        .windows(3)
+        .filter(|w| w[1][1] >= threshold && w[1][1] > w[0][1] && w[1][1] >= w[2][1])
+        .map(|w| w[1])
+        .collect()
+}
+
+/// Finds the index of the point of `points` (sorted by x) whose x is closest to `x`.
+pub fn nearest_index(points: &[[f64; 2]], x: f64) -> Option<usize> {
+    let i = points.partition_point(|p| p[0] < x);
+    match (i.checked_sub(1), points.get(i)) {
+        (Some(b), Some(a)) => Some(if x - points[b][0] <= a[0] - x { b } else { i }),
+        (Some(b), None) => Some(b),
+        (None, Some(_)) => Some(i),
+        (None, None) => None,
+    }
+}
+
+/// Total harmonic distortion of a signal with the amplitude spectrum `points`, taking the
+/// component at `fundamental` Hz as the fundamental.
+///
+/// This is the ratio of the root-sum-square amplitude of the harmonics to the amplitude of the
+/// fundamental. Returns `None` if the fundamental is silent or outside of the spectrum.
+pub fn thd(points: &[[f64; 2]], fundamental: f64) -> Option<f64> {
+    // Takes the largest of the bins around `f`, in case the peak fell between two of them.
+    let amplitude_at = |f: f64| {
+        let i = points.partition_point(|p| p[0] < f);
+        points[i.saturating_sub(1)..(i + 1).min(points.len())]
+            .iter()
+            .map(|p| p[1])
+            .fold(0.0_f64, f64::max)
+    };
+
+    let max_frequency = points.last()?[0];
+    if fundamental <= 0.0 || fundamental > max_frequency {
+        return None;
+    }
+    let fundamental_amplitude = amplitude_at(fundamental);
+    if fundamental_amplitude <= 0.0 {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let harmonics_power: f64 = (2..)
+        .map(|k| k as f64 * fundamental)
+        .take_while(|f| *f <= max_frequency)
+        .map(|f| amplitude_at(f).powi(2))
+        .sum();
+
+    Some(harmonics_power.sqrt() / fundamental_amplitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_mut_does_not_initialize() {
+        let mut cache = Cache::default();
+        assert_eq!(cache.get_mut(), None);
+        assert!(!cache.is_valid());
+
+        assert_eq!(*cache.get_or_init(|| 1), 1);
+        assert_eq!(cache.get_mut(), Some(&mut 1));
+        assert_eq!(*cache.get_or_init(|| 2), 1);
+    }
+
+    #[test]
+    fn generation_counts_invalidations() {
+        let mut cache = Cache::default();
+        assert_eq!(cache.generation(), 0);
+
+        // Reads and initialization leave the generation alone.
+        cache.get_or_init(|| 1);
+        cache.get_or_init(|| 2);
+        let _ = cache.get_mut();
+        assert_eq!(cache.generation(), 0);
+
+        cache.invalidate();
+        assert_eq!(cache.generation(), 1);
+        assert_eq!(cache.get_mut(), None);
+        cache.invalidate();
+        assert_eq!(cache.generation(), 2);
+
+        assert_eq!(*cache.get_or_init(|| 3), 3);
+        assert_eq!(cache.generation(), 2);
+    }
+
+    #[test]
+    fn plot_data_size_counts_every_channel_and_waveform() {
+        const POINT: usize = std::mem::size_of::<[f64; 2]>();
+        let mut pd = PlotData {
+            waveform: vec![[0.0; 2]; 10],
+            display_waveform: vec![[0.0; 2]; 4],
+            ..PlotData::default()
+        };
+        assert_eq!(pd.size_bytes(), 14 * POINT);
+
+        pd.continuous = Some(vec![[0.0; 2]; 20]);
+        assert_eq!(pd.size_bytes(), 34 * POINT);
+
+        pd.right = Some(RightChannel {
+            waveform: vec![[0.0; 2]; 10],
+            display_waveform: vec![[0.0; 2]; 4],
+            spectrum: Spectrum {
+                points: vec![[0.0; 2]; 6],
+                ..Spectrum::default()
+            },
+        });
+        assert_eq!(pd.size_bytes(), 54 * POINT);
+    }
+
+    #[test]
+    fn finds_peaks_at_or_above_threshold() {
+        let points: Vec<_> = [0.0, 2.0, 1.0, 0.5, 0.5, 3.0, 3.0, 1.0, 1.5]
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                #[allow(clippy::cast_precision_loss)]
+                let x = i as f64;
+                [x, value]
+            })
+            .collect();
+
+        // A plateau counts once, at its start. The ends have no neighbour on one side.
+        assert_eq!(find_peaks(&points, 1.0), [[1.0, 2.0], [5.0, 3.0]]);
+        assert_eq!(find_peaks(&points, 2.0), [[1.0, 2.0], [5.0, 3.0]]);
+        assert_eq!(find_peaks(&points, 2.5), [[5.0, 3.0]]);
+        assert!(find_peaks(&points, 3.5).is_empty());
+        // dB values are negative.
+        let db: Vec<_> = points.iter().map(|[x, y]| [*x, y - 100.0]).collect();
+        assert_eq!(find_peaks(&db, -98.5), [[1.0, -98.0], [5.0, -97.0]]);
+    }
 }
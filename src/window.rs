@@ -0,0 +1,87 @@
+//! Window functions applied to the signal before computing the spectrum.
+
+use std::f64::consts::TAU;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WindowFunction {
+    #[default]
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    BlackmanHarris,
+    /// Has almost no scalloping loss, so sinusoids read back their true amplitude even between
+    /// bins, at the cost of a wide main lobe.
+    FlatTop,
+}
+
+impl WindowFunction {
+    pub const ALL: [WindowFunction; 6] = [
+        WindowFunction::Rectangular,
+        WindowFunction::Hann,
+        WindowFunction::Hamming,
+        WindowFunction::Blackman,
+        WindowFunction::BlackmanHarris,
+        WindowFunction::FlatTop,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowFunction::Rectangular => "Rectangular",
+            WindowFunction::Hann => "Hann",
+            WindowFunction::Hamming => "Hamming",
+            WindowFunction::Blackman => "Blackman",
+            WindowFunction::BlackmanHarris => "Blackman-Harris",
+            WindowFunction::FlatTop => "Flat top",
+        }
+    }
+
+    /// Value of the `i`-th of `n` window coefficients.
+    ///
+    /// Uses the periodic ("DFT-even") form of each window, as is usual for spectral analysis.
+    pub fn coefficient(self, i: usize, n: usize) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let x = TAU * i as f64 / n as f64;
+        match self {
+            WindowFunction::Rectangular => 1.0,
+            WindowFunction::Hann => 0.5 - 0.5 * x.cos(),
+            WindowFunction::Hamming => 0.54 - 0.46 * x.cos(),
+            WindowFunction::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+            WindowFunction::BlackmanHarris => {
+                0.35875 - 0.48829 * x.cos() + 0.14128 * (2.0 * x).cos() - 0.01168 * (3.0 * x).cos()
+            }
+            WindowFunction::FlatTop => {
+                0.215_578_95 - 0.416_631_58 * x.cos() + 0.277_263_158 * (2.0 * x).cos()
+                    - 0.083_578_947 * (3.0 * x).cos()
+                    + 0.006_947_368 * (4.0 * x).cos()
+            }
+        }
+    }
+
+    /// All `n` window coefficients.
+    pub fn coefficients(self, n: usize) -> Vec<f64> {
+        (0..n).map(|i| self.coefficient(i, n)).collect()
+    }
+
+    /// Mean value of the window, i.e. the factor by which it scales the amplitude of a sinusoid.
+    pub fn coherent_gain(self, n: usize) -> f64 {
+        if n == 0 {
+            return 1.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let gain = self.coefficients(n).iter().sum::<f64>() / n as f64;
+        gain
+    }
+
+    /// Mean square of the window, i.e. the factor by which it scales the power of broadband noise.
+    pub fn power_gain(self, n: usize) -> f64 {
+        if n == 0 {
+            return 1.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let gain = self.coefficients(n).iter().map(|w| w * w).sum::<f64>() / n as f64;
+        gain
+    }
+}